@@ -0,0 +1,34 @@
+//! Generates the C header `praborrow.h` from `cbindgen.toml`.
+//!
+//! The header is written to `OUT_DIR`, and also to `$PRABORROW_HEADER_DIR`
+//! when that is set, for C builds that want it at a fixed location.
+
+use std::env;
+use std::path::PathBuf;
+
+const HEADER: &str = "praborrow.h";
+
+fn main() {
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    println!("cargo:rerun-if-changed=src");
+    println!("cargo:rerun-if-env-changed=PRABORROW_HEADER_DIR");
+
+    let crate_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR"));
+    let out_dir = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR"));
+
+    let config = cbindgen::Config::from_file(crate_dir.join("cbindgen.toml"))
+        .unwrap_or_else(|e| panic!("invalid cbindgen.toml: {e}"));
+    let bindings = cbindgen::Builder::new()
+        .with_crate(&crate_dir)
+        .with_config(config)
+        .generate()
+        .unwrap_or_else(|e| panic!("failed to generate {HEADER}: {e}"));
+
+    bindings.write_to_file(out_dir.join(HEADER));
+    if let Some(dir) = env::var_os("PRABORROW_HEADER_DIR") {
+        let dir = crate_dir.join(dir);
+        std::fs::create_dir_all(&dir)
+            .unwrap_or_else(|e| panic!("cannot create {}: {e}", dir.display()));
+        bindings.write_to_file(dir.join(HEADER));
+    }
+}
@@ -0,0 +1,103 @@
+//! Push-style delivery of outbox envoys to a foreign callback.
+
+use crate::Envelope;
+use std::cell::Cell;
+use std::ffi::c_void;
+use std::sync::{Condvar, Mutex, PoisonError};
+
+/// Foreign function that receives envoys as they are sent by Rust.
+///
+/// Called as `callback(user_data, id, data, len)`. `data` is only valid for
+/// the duration of the call; copy it if it must outlive the callback. `None`
+/// (NULL in C) where a callback is expected unregisters it.
+pub type EnvoyCallbackFn =
+    Option<unsafe extern "C" fn(user_data: *mut c_void, id: u32, data: *const u8, len: usize)>;
+
+/// A registered callback together with its foreign user data.
+#[derive(Clone, Copy)]
+pub(crate) struct EnvoyCallback {
+    pub(crate) func: unsafe extern "C" fn(*mut c_void, u32, *const u8, usize),
+    pub(crate) user_data: *mut c_void,
+}
+
+// SAFETY: the foreign side promises, by registering, that `user_data` may be
+// used from whichever thread sends an envoy.
+unsafe impl Send for EnvoyCallback {}
+unsafe impl Sync for EnvoyCallback {}
+
+thread_local! {
+    /// Number of envoy callbacks currently running on this thread.
+    static CALLBACK_DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+#[derive(Default)]
+struct SlotState {
+    callback: Option<EnvoyCallback>,
+    in_flight: usize,
+}
+
+/// Holds the callback of one context and tracks invocations in progress.
+///
+/// Replacing the callback waits until no invocation is running, so the
+/// foreign side may release `user_data` as soon as unregistration returns.
+/// The wait is skipped when called from inside a callback, which would
+/// otherwise wait on itself.
+#[derive(Default)]
+pub(crate) struct CallbackSlot {
+    state: Mutex<SlotState>,
+    idle: Condvar,
+}
+
+impl CallbackSlot {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the callback and returns once no old invocation is running.
+    pub(crate) fn set(&self, callback: Option<EnvoyCallback>) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.callback = callback;
+
+        if CALLBACK_DEPTH.with(Cell::get) > 0 {
+            return;
+        }
+        while state.in_flight > 0 {
+            state = self
+                .idle
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// Hands `envelope` to the callback, or gives it back if none is registered.
+    pub(crate) fn deliver(&self, envelope: Envelope) -> Option<Envelope> {
+        let callback = {
+            let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+            let Some(callback) = state.callback else {
+                return Some(envelope);
+            };
+            state.in_flight += 1;
+            callback
+        };
+
+        CALLBACK_DEPTH.with(|depth| depth.set(depth.get() + 1));
+        // SAFETY: registration promises that `func` accepts these arguments;
+        // the payload outlives the call.
+        unsafe {
+            (callback.func)(
+                callback.user_data,
+                envelope.id,
+                envelope.payload.as_ptr(),
+                envelope.payload.len(),
+            )
+        };
+        CALLBACK_DEPTH.with(|depth| depth.set(depth.get() - 1));
+
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.in_flight -= 1;
+        if state.in_flight == 0 {
+            self.idle.notify_all();
+        }
+        None
+    }
+}
@@ -0,0 +1,277 @@
+//! Runtime configuration of diplomatic queues.
+
+use crate::MAX_QUEUE_DEPTH;
+use crate::envelope::{PRIORITY_LANES, Priority};
+use crate::safe::DiplomacyError;
+use std::time::Duration;
+
+/// Default byte budget of each queue: 64 MiB of queued payload.
+pub const DEFAULT_MAX_BYTES: usize = 64 * 1024 * 1024;
+
+/// What a full queue does with one more envoy.
+///
+/// A plain integer rather than a Rust enum so that any value written by C is
+/// representable; unknown values are rejected when the configuration is used.
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OverflowPolicy(pub u32);
+
+impl OverflowPolicy {
+    /// Refuse the new envoy with `ERR_QUEUE_FULL` / [`DiplomacyError::QueueFull`].
+    pub const REJECT: Self = Self(0);
+    /// Discard the oldest queued envoys of the same or a lower priority until
+    /// the new one fits, and refuse it if there are none.
+    pub const DROP_OLDEST: Self = Self(1);
+    /// Wait up to `block_timeout_ms` for room, then refuse.
+    pub const BLOCK: Self = Self(2);
+    /// Hand the new envoy to the registered spill handler instead of queuing it.
+    /// Without a spill handler this behaves like [`OverflowPolicy::REJECT`].
+    pub const SPILL: Self = Self(3);
+
+    fn is_known(self) -> bool {
+        self.0 <= Self::SPILL.0
+    }
+}
+
+impl Default for OverflowPolicy {
+    fn default() -> Self {
+        Self::REJECT
+    }
+}
+
+/// Limits of a single queue (the incoming queue or the outbox).
+///
+/// An envoy overflows when accepting it would exceed either limit; `overflow`
+/// decides what happens then. Use `usize::MAX` (`SIZE_MAX` in C) to lift a limit.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueConfig {
+    /// Maximum number of queued envoys.
+    pub max_depth: usize,
+    /// Maximum total payload bytes of queued envoys.
+    pub max_bytes: usize,
+    /// What to do with an envoy that does not fit.
+    pub overflow: OverflowPolicy,
+    /// How long [`OverflowPolicy::BLOCK`] waits for room; `u32::MAX` waits indefinitely.
+    pub block_timeout_ms: u32,
+    /// Maximum number of queued envoys per priority lane, indexed by
+    /// [`Priority`]. `max_depth` still bounds the lanes together.
+    pub lane_depth: [usize; PRIORITY_LANES],
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        Self {
+            max_depth: MAX_QUEUE_DEPTH,
+            max_bytes: DEFAULT_MAX_BYTES,
+            overflow: OverflowPolicy::REJECT,
+            block_timeout_ms: 1_000,
+            lane_depth: [MAX_QUEUE_DEPTH; PRIORITY_LANES],
+        }
+    }
+}
+
+/// When to warn about envoys the foreign jurisdiction has not returned.
+///
+/// Both warnings are off by default; the loans can always be inspected with
+/// `praborrow_outstanding_loans` / [`Diplomat::loan_report`](crate::safe::Diplomat::loan_report).
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LoanConfig {
+    /// Log a warning when this many envoys are on loan at once; 0 never warns.
+    pub warn_threshold: usize,
+    /// Log a warning listing the envoys still on loan when relations are severed.
+    pub warn_on_sever: bool,
+}
+
+/// How debug logs show the payload of an envoy; an integer like [`OverflowPolicy`].
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PayloadLogMode(pub u32);
+
+impl PayloadLogMode {
+    /// Log only the payload length.
+    pub const OMIT: Self = Self(0);
+    /// Log the whole payload.
+    pub const FULL: Self = Self(1);
+    /// Log the first `max_bytes` bytes of the payload.
+    pub const TRUNCATED: Self = Self(2);
+    /// Log a 64-bit FNV-1a hash of the payload, which tells equal payloads
+    /// apart without revealing them.
+    pub const HASHED: Self = Self(3);
+
+    fn is_known(self) -> bool {
+        self.0 <= Self::HASHED.0
+    }
+}
+
+impl Default for PayloadLogMode {
+    fn default() -> Self {
+        Self::OMIT
+    }
+}
+
+/// How envoys sent and received are logged at debug level.
+///
+/// Payloads may hold secrets and be large, so by default only their length
+/// is logged.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PayloadLogConfig {
+    /// How the payload is shown.
+    pub mode: PayloadLogMode,
+    /// How many payload bytes [`PayloadLogMode::TRUNCATED`] shows.
+    pub max_bytes: usize,
+}
+
+impl Default for PayloadLogConfig {
+    fn default() -> Self {
+        Self {
+            mode: PayloadLogMode::OMIT,
+            max_bytes: 64,
+        }
+    }
+}
+
+/// Configuration of a diplomatic context.
+///
+/// Build it in Rust with the chained setters:
+///
+/// ```
+/// use praborrow_diplomacy::DiplomacyConfig;
+///
+/// let config = DiplomacyConfig::new()
+///     .incoming_depth(1_000)
+///     .outbox_bytes(8 * 1024 * 1024);
+/// assert_eq!(config.incoming.max_depth, 1_000);
+/// ```
+///
+/// C callers start from `praborrow_config_default()` and overwrite fields.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DiplomacyConfig {
+    /// Envoys from the foreign jurisdiction waiting for Rust.
+    pub incoming: QueueConfig,
+    /// Envoys from Rust waiting for the foreign jurisdiction.
+    pub outbox: QueueConfig,
+    /// Warnings about envoys the foreign jurisdiction does not return.
+    pub loans: LoanConfig,
+    /// How payloads appear in debug logs.
+    pub payload_log: PayloadLogConfig,
+}
+
+impl DiplomacyConfig {
+    /// The default configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum number of envoys waiting for Rust.
+    pub fn incoming_depth(mut self, max_depth: usize) -> Self {
+        self.incoming.max_depth = max_depth;
+        self
+    }
+
+    /// Sets the maximum payload bytes waiting for Rust.
+    pub fn incoming_bytes(mut self, max_bytes: usize) -> Self {
+        self.incoming.max_bytes = max_bytes;
+        self
+    }
+
+    /// Sets the maximum number of envoys waiting for the foreign jurisdiction.
+    pub fn outbox_depth(mut self, max_depth: usize) -> Self {
+        self.outbox.max_depth = max_depth;
+        self
+    }
+
+    /// Sets the maximum payload bytes waiting for the foreign jurisdiction.
+    pub fn outbox_bytes(mut self, max_bytes: usize) -> Self {
+        self.outbox.max_bytes = max_bytes;
+        self
+    }
+
+    /// Sets what a full incoming queue does with one more envoy from C.
+    pub fn incoming_overflow(mut self, policy: OverflowPolicy) -> Self {
+        self.incoming.overflow = policy;
+        self
+    }
+
+    /// Sets what a full outbox does with one more envoy from Rust.
+    pub fn outbox_overflow(mut self, policy: OverflowPolicy) -> Self {
+        self.outbox.overflow = policy;
+        self
+    }
+
+    /// Sets how long [`OverflowPolicy::BLOCK`] waits for incoming room.
+    pub fn incoming_block_timeout(mut self, timeout: Duration) -> Self {
+        self.incoming.block_timeout_ms = duration_to_ms(timeout);
+        self
+    }
+
+    /// Sets how long [`OverflowPolicy::BLOCK`] waits for outbox room.
+    pub fn outbox_block_timeout(mut self, timeout: Duration) -> Self {
+        self.outbox.block_timeout_ms = duration_to_ms(timeout);
+        self
+    }
+
+    /// Sets the maximum number of envoys waiting for Rust in one priority lane.
+    pub fn incoming_lane_depth(mut self, priority: Priority, max_depth: usize) -> Self {
+        self.incoming.lane_depth[priority.lane()] = max_depth;
+        self
+    }
+
+    /// Sets the maximum number of envoys waiting for the foreign jurisdiction
+    /// in one priority lane.
+    pub fn outbox_lane_depth(mut self, priority: Priority, max_depth: usize) -> Self {
+        self.outbox.lane_depth[priority.lane()] = max_depth;
+        self
+    }
+
+    /// Warns once `threshold` envoys are on loan to the foreign jurisdiction at
+    /// the same time; 0 disables the warning.
+    pub fn loan_warn_threshold(mut self, threshold: usize) -> Self {
+        self.loans.warn_threshold = threshold;
+        self
+    }
+
+    /// Warns at shutdown about envoys the foreign jurisdiction never returned.
+    pub fn warn_on_leaked_loans(mut self, warn: bool) -> Self {
+        self.loans.warn_on_sever = warn;
+        self
+    }
+
+    /// Sets how payloads appear in debug logs.
+    pub fn payload_log_mode(mut self, mode: PayloadLogMode) -> Self {
+        self.payload_log.mode = mode;
+        self
+    }
+
+    /// Sets how many payload bytes [`PayloadLogMode::TRUNCATED`] logs.
+    pub fn payload_log_bytes(mut self, max_bytes: usize) -> Self {
+        self.payload_log.max_bytes = max_bytes;
+        self
+    }
+
+    /// Rejects limits that would make a queue unusable and unknown policies
+    /// or payload log modes.
+    pub(crate) fn validate(&self) -> Result<(), DiplomacyError> {
+        if !self.payload_log.mode.is_known() {
+            return Err(DiplomacyError::InvalidConfig);
+        }
+        for queue in [&self.incoming, &self.outbox] {
+            if queue.max_depth == 0
+                || queue.max_bytes == 0
+                || queue.lane_depth.contains(&0)
+                || !queue.overflow.is_known()
+            {
+                return Err(DiplomacyError::InvalidConfig);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Saturates to `u32::MAX`, which waits indefinitely.
+fn duration_to_ms(timeout: Duration) -> u32 {
+    u32::try_from(timeout.as_millis()).unwrap_or(u32::MAX)
+}
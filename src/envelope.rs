@@ -0,0 +1,229 @@
+//! Structured envoys exchanged between Rust and the foreign jurisdiction.
+
+use crate::safe::DiplomacyError;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Number of priority lanes in every queue.
+pub const PRIORITY_LANES: usize = 3;
+
+/// Delivery priority of an envoy.
+///
+/// Each queue keeps one FIFO lane per priority and receivers take from the
+/// highest non-empty lane first. C passes the numeric value.
+#[repr(u8)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize,
+)]
+pub enum Priority {
+    /// Bulk traffic that may wait behind everything else.
+    Low = 0,
+    /// The priority of plain `send_envoy` and `Diplomat::send`.
+    #[default]
+    Normal = 1,
+    /// Control traffic, such as a request to stop.
+    High = 2,
+}
+
+impl Priority {
+    /// Index of this priority's lane.
+    pub(crate) fn lane(self) -> usize {
+        self as usize
+    }
+}
+
+impl TryFrom<u8> for Priority {
+    type Error = u8;
+
+    /// Fails with the offending value if it names no lane.
+    fn try_from(value: u8) -> Result<Self, u8> {
+        match value {
+            0 => Ok(Self::Low),
+            1 => Ok(Self::Normal),
+            2 => Ok(Self::High),
+            other => Err(other),
+        }
+    }
+}
+
+/// Per-envoy delivery options, for `send_envoy_with_options` and
+/// [`Diplomat::send_with`](crate::safe::Diplomat::send_with).
+///
+/// Zero fields mean "not set", so a zeroed struct sends like `send_envoy`.
+/// An envoy still queued when its TTL or deadline passes is skipped by
+/// receivers and counted as expired; the earlier of the two applies.
+///
+/// ```
+/// use praborrow_diplomacy::{Priority, SendOptions};
+/// use std::time::Duration;
+///
+/// let options = SendOptions::new()
+///     .priority(Priority::High)
+///     .ttl(Duration::from_millis(250));
+/// assert_eq!(options.ttl_ms, 250);
+/// ```
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SendOptions {
+    /// Numeric [`Priority`] of the envoy.
+    pub priority: u8,
+    /// Milliseconds the envoy may wait in a queue; 0 for no limit.
+    pub ttl_ms: u32,
+    /// Absolute deadline in microseconds since the Unix epoch; 0 for none.
+    pub deadline_us: u64,
+}
+
+impl Default for SendOptions {
+    fn default() -> Self {
+        Self {
+            priority: Priority::Normal as u8,
+            ttl_ms: 0,
+            deadline_us: 0,
+        }
+    }
+}
+
+impl SendOptions {
+    /// Options of a plain send: normal priority, never expires.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the lane the envoy travels in.
+    pub fn priority(mut self, priority: Priority) -> Self {
+        self.priority = priority as u8;
+        self
+    }
+
+    /// Sets how long the envoy may wait in a queue, saturating at `u32::MAX` ms.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl_ms = u32::try_from(ttl.as_millis()).unwrap_or(u32::MAX);
+        self
+    }
+
+    /// Sets the moment after which the envoy is no longer worth delivering.
+    pub fn deadline(mut self, deadline: SystemTime) -> Self {
+        self.deadline_us = micros_since_epoch(deadline).max(1);
+        self
+    }
+
+    /// Validates the options and fixes the expiry relative to now.
+    ///
+    /// Expiry is measured on the monotonic clock, so that changes to the wall
+    /// clock after sending neither expire envoys early nor keep them forever.
+    /// Only the absolute deadline is read against the wall clock, once, here.
+    pub(crate) fn resolve(&self) -> Result<Routing, DiplomacyError> {
+        let priority =
+            Priority::try_from(self.priority).map_err(|_| DiplomacyError::InvalidPriority)?;
+        let now = Instant::now();
+        let ttl = (self.ttl_ms != 0).then(|| now + Duration::from_millis(u64::from(self.ttl_ms)));
+        let deadline = (self.deadline_us != 0).then(|| {
+            let deadline = UNIX_EPOCH + Duration::from_micros(self.deadline_us);
+            // A deadline already passed leaves no time at all.
+            now + deadline
+                .duration_since(SystemTime::now())
+                .unwrap_or_default()
+        });
+        Ok(Routing {
+            priority,
+            expires_at: ttl.into_iter().chain(deadline).min(),
+        })
+    }
+}
+
+/// Resolved [`SendOptions`] stamped onto an envelope when it is sealed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct Routing {
+    pub(crate) priority: Priority,
+    pub(crate) expires_at: Option<Instant>,
+}
+
+impl Routing {
+    pub(crate) fn with_priority(priority: Priority) -> Self {
+        Self {
+            priority,
+            expires_at: None,
+        }
+    }
+}
+
+/// A single envoy as it sits in a diplomatic queue.
+///
+/// Both directions carry the same structure, so consumers read the id and
+/// metadata directly instead of re-parsing a formatted string.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Envelope {
+    /// Identifier chosen by the sender.
+    pub id: u32,
+    /// Position of this envoy in its context, assigned when it was queued.
+    pub sequence: u64,
+    /// When the envoy was queued.
+    pub timestamp: SystemTime,
+    /// Lane the envoy travels in.
+    #[serde(default)]
+    pub priority: Priority,
+    /// When the envoy stops being worth delivering, if ever.
+    ///
+    /// An `Instant` only means something in this process, so it is not
+    /// serialized.
+    #[serde(skip)]
+    pub expires_at: Option<Instant>,
+    /// Raw payload bytes.
+    pub payload: Vec<u8>,
+}
+
+impl Envelope {
+    pub(crate) fn new(id: u32, sequence: u64, routing: Routing, payload: Vec<u8>) -> Self {
+        Self {
+            id,
+            sequence,
+            timestamp: SystemTime::now(),
+            priority: routing.priority,
+            expires_at: routing.expires_at,
+            payload,
+        }
+    }
+
+    /// Microseconds since the Unix epoch, as reported to C.
+    pub fn timestamp_micros(&self) -> u64 {
+        micros_since_epoch(self.timestamp)
+    }
+
+    /// Whether the envoy's TTL or deadline has passed.
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| Instant::now() >= expires_at)
+    }
+}
+
+fn micros_since_epoch(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| u64::try_from(d.as_micros()).unwrap_or(u64::MAX))
+        .unwrap_or(0)
+}
+
+/// C view of an [`Envelope`], filled by `receive_envoy_envelope`.
+///
+/// `data` is on loan to the caller and must be released with `free_envoy_bytes`.
+#[repr(C)]
+#[derive(Debug)]
+pub struct Envoy {
+    pub id: u32,
+    pub sequence: u64,
+    /// Microseconds since the Unix epoch.
+    pub timestamp_us: u64,
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+impl Envoy {
+    pub(crate) fn empty() -> Self {
+        Self {
+            id: 0,
+            sequence: 0,
+            timestamp_us: 0,
+            data: std::ptr::null_mut(),
+            len: 0,
+        }
+    }
+}
@@ -0,0 +1,53 @@
+//! Reporting of envoys that expired before anyone received them.
+
+use crate::callback::{CallbackSlot, EnvoyCallback};
+use crate::{Direction, Envelope, ExpiryHandler};
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::sync::{Arc, PoisonError, RwLock};
+
+/// The Rust handler and foreign callback told about expired envoys.
+///
+/// Shared by every mailbox of a context, which report expiries as they skip
+/// them on dequeue.
+#[derive(Default)]
+pub(crate) struct ExpiryHooks {
+    handler: RwLock<Option<Arc<dyn ExpiryHandler>>>,
+    callback: CallbackSlot,
+}
+
+impl ExpiryHooks {
+    pub(crate) fn set_handler(&self, handler: Option<Arc<dyn ExpiryHandler>>) {
+        *self.handler.write().unwrap_or_else(PoisonError::into_inner) = handler;
+    }
+
+    /// Replaces the foreign callback; returns once no old invocation is running.
+    pub(crate) fn set_callback(&self, callback: Option<EnvoyCallback>) {
+        self.callback.set(callback);
+    }
+
+    /// Reports an envelope that was discarded because it expired.
+    ///
+    /// A panicking handler is logged and otherwise ignored, so that the panic
+    /// never unwinds into a receiver.
+    pub(crate) fn notify(&self, direction: Direction, envelope: Envelope) {
+        tracing::debug!(
+            envoy_id = envelope.id,
+            sequence = envelope.sequence,
+            ?direction,
+            "Expired envoy discarded"
+        );
+
+        let handler = self
+            .handler
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        if let Some(handler) = handler
+            && catch_unwind(AssertUnwindSafe(|| handler.expired(direction, &envelope))).is_err()
+        {
+            tracing::error!(envoy_id = envelope.id, "Panic caught in expiry handler");
+        }
+
+        self.callback.deliver(envelope);
+    }
+}
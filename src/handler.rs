@@ -0,0 +1,103 @@
+//! Rust-side handlers that decide how to answer incoming envoys and where
+//! overflowing envoys go.
+
+use crate::Envelope;
+
+/// Decides what, if anything, goes back to the foreign jurisdiction for each
+/// envoy it sends.
+///
+/// The handler runs on the foreign caller's thread inside `send_envoy`, after
+/// the envoy has been queued for Rust. Returning `Some(payload)` queues a reply
+/// with the same id in the outbox; returning `None` sends nothing. Keep it
+/// short: the foreign call does not return until the handler does.
+pub trait ReplyHandler: Send + Sync {
+    fn reply(&self, envelope: &Envelope) -> Option<Vec<u8>>;
+}
+
+impl<F> ReplyHandler for F
+where
+    F: Fn(&Envelope) -> Option<Vec<u8>> + Send + Sync,
+{
+    fn reply(&self, envelope: &Envelope) -> Option<Vec<u8>> {
+        self(envelope)
+    }
+}
+
+/// Echoes every envoy back prefixed with `"Ack: "`.
+///
+/// This was the built-in behavior of `send_envoy` before handlers were
+/// pluggable; it is useful for smoke-testing a foreign integration.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EchoHandler;
+
+impl ReplyHandler for EchoHandler {
+    fn reply(&self, envelope: &Envelope) -> Option<Vec<u8>> {
+        let mut ack = b"Ack: ".to_vec();
+        ack.extend_from_slice(&envelope.payload);
+        Some(ack)
+    }
+}
+
+/// Answers calls made by the foreign jurisdiction with `praborrow_call`.
+///
+/// Runs on the thread that called `serve`, not on the foreign caller's
+/// thread. The request's `sequence` is its correlation id. A handler that
+/// panics fails the call with `ERR_PANIC`; one that outlasts the caller's
+/// timeout has its reply discarded.
+pub trait RpcHandler: Send + Sync {
+    fn handle(&self, request: &Envelope) -> Vec<u8>;
+}
+
+impl<F> RpcHandler for F
+where
+    F: Fn(&Envelope) -> Vec<u8> + Send + Sync,
+{
+    fn handle(&self, request: &Envelope) -> Vec<u8> {
+        self(request)
+    }
+}
+
+/// Told about every envoy discarded because its TTL or deadline passed
+/// while it was queued.
+///
+/// Runs on the receiving thread, in place of delivering the envoy.
+pub trait ExpiryHandler: Send + Sync {
+    fn expired(&self, direction: Direction, envelope: &Envelope);
+}
+
+impl<F> ExpiryHandler for F
+where
+    F: Fn(Direction, &Envelope) + Send + Sync,
+{
+    fn expired(&self, direction: Direction, envelope: &Envelope) {
+        self(direction, envelope)
+    }
+}
+
+/// The queue an envoy was travelling through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// From the foreign jurisdiction to Rust.
+    Incoming,
+    /// From Rust to the foreign jurisdiction.
+    Outbox,
+}
+
+/// Takes envoys that arrive at a full queue configured with
+/// [`OverflowPolicy::SPILL`](crate::config::OverflowPolicy::SPILL).
+///
+/// The handler runs on the sender's thread in place of queuing the envoy; the
+/// send still succeeds. It typically writes the envoy to disk or to a slower
+/// secondary queue, to be replayed once the backlog clears.
+pub trait SpillHandler: Send + Sync {
+    fn spill(&self, direction: Direction, envelope: Envelope);
+}
+
+impl<F> SpillHandler for F
+where
+    F: Fn(Direction, Envelope) + Send + Sync,
+{
+    fn spill(&self, direction: Direction, envelope: Envelope) {
+        self(direction, envelope)
+    }
+}
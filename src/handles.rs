@@ -0,0 +1,152 @@
+//! Envoys loaned to the foreign jurisdiction by handle rather than by pointer.
+
+use crate::Envelope;
+use crate::loans::LoanInfo;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::time::Instant;
+
+/// Handle to an envoy loaned to the foreign jurisdiction.
+///
+/// The low 32 bits index a slot of the context's handle table and the high
+/// 32 bits carry the generation of the loan, drawn from a counter shared by
+/// every table in the process. A handle that was released, that belongs to an
+/// envoy whose slot has since been reused, or that comes from another context
+/// or an earlier session therefore does not match until 2^32 more loans have
+/// been made. `0` is never a valid handle.
+#[repr(transparent)]
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy, Default)]
+pub struct EnvoyHandle(pub u64);
+
+impl EnvoyHandle {
+    /// The handle returned when there is no envoy.
+    pub const NONE: Self = Self(0);
+
+    fn new(index: u32, generation: u32) -> Self {
+        Self(u64::from(generation) << 32 | u64::from(index))
+    }
+
+    fn index(self) -> usize {
+        (self.0 & u64::from(u32::MAX)) as usize
+    }
+
+    fn generation(self) -> u32 {
+        (self.0 >> 32) as u32
+    }
+}
+
+/// Generation of the next loan in any table; 0 is skipped when it wraps.
+static NEXT_GENERATION: AtomicU32 = AtomicU32::new(1);
+
+fn next_generation() -> u32 {
+    loop {
+        let generation = NEXT_GENERATION.fetch_add(1, Ordering::Relaxed);
+        if generation != 0 {
+            return generation;
+        }
+    }
+}
+
+/// One entry of a [`HandleTable`].
+struct Slot {
+    /// Generation of the current or most recent loan.
+    generation: u32,
+    /// The loaned envelope and when it was lent.
+    envelope: Option<(Envelope, Instant)>,
+}
+
+#[derive(Default)]
+struct TableState {
+    slots: Vec<Slot>,
+    /// Indexes of free slots, reused most recently freed first.
+    free: Vec<u32>,
+    loaned: usize,
+}
+
+/// Envelopes on loan to the foreign jurisdiction, addressed by [`EnvoyHandle`].
+///
+/// Unlike pointer loans, which are keyed by address and can collide with a
+/// later allocation at the same address, every loan gets a fresh process-wide
+/// generation, so a handle of another table does not match.
+#[derive(Default)]
+pub(crate) struct HandleTable {
+    state: Mutex<TableState>,
+}
+
+impl HandleTable {
+    /// Takes ownership of `envelope` until the returned handle is released.
+    pub(crate) fn lend(&self, envelope: Envelope) -> EnvoyHandle {
+        let generation = next_generation();
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.loaned += 1;
+        if let Some(index) = state.free.pop() {
+            let slot = &mut state.slots[index as usize];
+            slot.generation = generation;
+            slot.envelope = Some((envelope, Instant::now()));
+            return EnvoyHandle::new(index, generation);
+        }
+
+        let index = u32::try_from(state.slots.len()).expect("handle table exhausted");
+        state.slots.push(Slot {
+            generation,
+            envelope: Some((envelope, Instant::now())),
+        });
+        EnvoyHandle::new(index, generation)
+    }
+
+    /// Runs `f` on the envelope behind `handle`, or returns `None` for a
+    /// handle that is not currently on loan.
+    pub(crate) fn with<R>(&self, handle: EnvoyHandle, f: impl FnOnce(&Envelope) -> R) -> Option<R> {
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state
+            .slots
+            .get(handle.index())
+            .filter(|slot| slot.generation == handle.generation())
+            .and_then(|slot| slot.envelope.as_ref())
+            .map(|(envelope, _)| f(envelope))
+    }
+
+    /// Ends the loan of `handle` and returns its envelope, or `None` if the
+    /// handle is not currently on loan.
+    pub(crate) fn release(&self, handle: EnvoyHandle) -> Option<Envelope> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let slot = state
+            .slots
+            .get_mut(handle.index())
+            .filter(|slot| slot.generation == handle.generation())?;
+        let (envelope, _) = slot.envelope.take()?;
+        state.free.push(handle.index() as u32);
+        state.loaned -= 1;
+        Some(envelope)
+    }
+
+    /// Appends a description of every handle on loan, as seen at `now`, to `loans`.
+    pub(crate) fn report_into(&self, loans: &mut Vec<LoanInfo>, now: Instant) {
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let loaned = state.slots.iter().filter_map(|slot| slot.envelope.as_ref());
+        loans.extend(loaned.map(|(envelope, lent_at)| {
+            LoanInfo::new(envelope.id, envelope.payload.len(), *lent_at, now)
+        }));
+    }
+
+    /// Number of handles currently on loan.
+    pub(crate) fn len(&self) -> usize {
+        self.state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .loaned
+    }
+}
+
+impl Drop for HandleTable {
+    /// Leaks the payloads still on loan, like unreturned pointers: the foreign
+    /// side may still be reading them through `envoy_data`.
+    fn drop(&mut self) {
+        let state = self.state.get_mut().unwrap_or_else(PoisonError::into_inner);
+        for slot in &mut state.slots {
+            if let Some((envelope, _)) = slot.envelope.take() {
+                std::mem::forget(envelope.payload);
+            }
+        }
+    }
+}
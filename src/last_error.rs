@@ -0,0 +1,58 @@
+//! Detail of the most recent failed call, kept per thread for C callers.
+
+use crate::stats;
+use std::cell::RefCell;
+use std::fmt::Display;
+use std::os::raw::c_int;
+
+/// A failed call: what returned which code, for which envoy, and why.
+struct Failure {
+    code: c_int,
+    function: &'static str,
+    envoy_id: Option<u32>,
+    reason: String,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<Failure>> = const { RefCell::new(None) };
+}
+
+/// Records that `function` failed with `code` on this thread, counts the
+/// failure and returns `code`.
+///
+/// Like `errno`, the record is only replaced by the next failure; successful
+/// calls leave it alone.
+pub(crate) fn fail(
+    code: c_int,
+    function: &'static str,
+    envoy_id: Option<u32>,
+    reason: impl Display,
+) -> c_int {
+    let failure = Failure {
+        code,
+        function,
+        envoy_id,
+        reason: reason.to_string(),
+    };
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(failure));
+    stats::count_reject(code);
+    code
+}
+
+/// Code of the most recent failure on this thread, or 0 if there was none.
+pub(crate) fn code() -> c_int {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(0, |failure| failure.code))
+}
+
+/// Description of the most recent failure on this thread, if any, such as
+/// `send_envoy (envoy 7): invalid UTF-8 at byte 3`.
+pub(crate) fn message() -> Option<String> {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map(|failure| match failure.envoy_id {
+                Some(id) => format!("{} (envoy {id}): {}", failure.function, failure.reason),
+                None => format!("{}: {}", failure.function, failure.reason),
+            })
+    })
+}
@@ -3,65 +3,98 @@
 //! Provides C-compatible functions for establishing inter-process communication
 //! with non-Rust systems.
 //!
-//! # C Header Declaration
+//! # C Header
 //!
-//! To use these functions from C, declare them as:
+//! `build.rs` generates `praborrow.h` with cbindgen from the exported items and
+//! their doc comments, as configured by `cbindgen.toml`. It is written to
+//! Cargo's `OUT_DIR`; set `PRABORROW_HEADER_DIR` (relative to this crate) to
+//! also get a copy at a fixed location:
 //!
-//! ```c
-//! // praborrow.h
-//! #ifndef PRABORROW_H
-//! #define PRABORROW_H
-//!
-//! #include <stdint.h>
-//!
-//! // Initialize diplomatic relations with the PraBorrow runtime
-//! // Returns: 0 on success, negative value on error
-//! int32_t establish_relations(void);
-//!
-//! // Alternative name for establish_relations
-//! int32_t init_ffi(void);
+//! ```sh
+//! PRABORROW_HEADER_DIR=include cargo build --release
+//! ```
 //!
-//! // Send an envoy (notification) to foreign jurisdiction
-//! // Returns: 0 on success, negative value on error
-//! int32_t send_envoy(uint32_t id, const char* payload);
+//! ```c
+//! #include "praborrow.h"
 //!
-//! // Receive an envoy from the PraBorrow runtime
-//! // Returns: pointer to C string (caller must free), or NULL if no envoys
-//! char* receive_envoy(void);
+//! if (establish_relations() != PRABORROW_STATUS_SUCCESS) {
+//!     fprintf(stderr, "praborrow %s: %s\n", praborrow_version(),
+//!             praborrow_strerror(praborrow_last_error_code()));
+//! }
+//! ```
 //!
-//! // Free a string returned by receive_envoy
-//! void free_envoy(char* envoy);
+//! # ABI Stability
 //!
-//! #endif // PRABORROW_H
-//! ```
+//! `abi/praborrow.abi` records the declarations of the header as of the
+//! [`ABI_VERSION`] it names. A test fails when those declarations change while
+//! `ABI_VERSION` stays the same; the crate version plays no part. After bumping
+//! `ABI_VERSION`, run the tests with `PRABORROW_BLESS_ABI=1` to record the new
+//! declarations. Set `PRABORROW_CHECK_C_HEADER=1` to also compile a C program
+//! against the header with `CC` (default `cc`).
 
-use crossbeam_queue::SegQueue;
-use dashmap::DashSet;
-use std::ffi::{CStr, CString};
+use callback::{CallbackSlot, EnvoyCallback};
+use dashmap::DashMap;
+use envelope::Routing;
+use expiry::ExpiryHooks;
+use loans::{Loan, LoanKind, LoanLedger};
+use mailbox::{Admission, Mailbox};
+use std::ffi::{CStr, CString, c_void};
 use std::os::raw::{c_char, c_int};
-use std::panic::catch_unwind;
-use std::sync::OnceLock;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::sync::atomic::Ordering;
+use std::sync::atomic::{AtomicBool, AtomicU64};
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::{Duration, Instant};
 
-/// Handle to track active envoys (pointers) given to foreign jurisdictions.
-/// Limits the scope of potential FFI misuse (double-free).
-use std::sync::atomic::{AtomicUsize, Ordering};
-
-/// Handle to track active envoys (pointers) given to foreign jurisdictions.
-/// Limits the scope of potential FFI misuse (double-free).
-#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
-pub struct EnvoyHandle(usize);
-
-// Error Codes
-const SUCCESS: c_int = 0;
+// Status codes, as returned to C
+const SUCCESS: c_int = PraborrowStatus::Success.code();
+const EMPTY: c_int = PraborrowStatus::Empty.code();
 
+pub mod callback;
+pub mod config;
+pub mod envelope;
+mod expiry;
+pub mod handler;
+mod handles;
+mod last_error;
+pub mod loans;
+pub mod logging;
+mod mailbox;
+#[cfg(unix)]
+mod readiness;
+mod rpc;
 pub mod safe;
-const ERR_ALREADY_INIT: c_int = -1;
-const ERR_INIT_FAILED: c_int = -2;
-const ERR_NULL_PTR: c_int = -3;
-const ERR_INVALID_UTF8: c_int = -4;
-const ERR_INVALID_ID: c_int = -5;
-const ERR_PANIC: c_int = -6;
-const ERR_QUEUE_FULL: c_int = -7;
+pub mod stats;
+pub mod status;
+#[cfg(feature = "async")]
+pub mod stream;
+
+pub use callback::EnvoyCallbackFn;
+pub use config::{
+    DiplomacyConfig, LoanConfig, OverflowPolicy, PayloadLogConfig, PayloadLogMode, QueueConfig,
+};
+pub use envelope::{Envelope, Envoy, PRIORITY_LANES, Priority, SendOptions};
+pub use handler::{Direction, EchoHandler, ExpiryHandler, ReplyHandler, RpcHandler, SpillHandler};
+pub use handles::EnvoyHandle;
+pub use loans::{LoanInfo, LoanReport, LoanSummary};
+pub use logging::{LogCallbackFn, LogLevel};
+pub use stats::{DiplomacyStats, OverflowCounts, OverflowReport, QueueStats, REJECT_CODES};
+pub use status::PraborrowStatus;
+const ERR_ALREADY_INIT: c_int = PraborrowStatus::AlreadyInitialized.code();
+const ERR_NOT_INITIALIZED: c_int = PraborrowStatus::NotInitialized.code();
+const ERR_NULL_PTR: c_int = PraborrowStatus::NullPointer.code();
+const ERR_INVALID_UTF8: c_int = PraborrowStatus::InvalidUtf8.code();
+const ERR_INVALID_ID: c_int = PraborrowStatus::InvalidId.code();
+const ERR_PANIC: c_int = PraborrowStatus::Panicked.code();
+const ERR_QUEUE_FULL: c_int = PraborrowStatus::QueueFull.code();
+const ERR_CLOSED: c_int = PraborrowStatus::Closed.code();
+const ERR_FD_UNAVAILABLE: c_int = PraborrowStatus::FdUnavailable.code();
+const ERR_INVALID_CONFIG: c_int = PraborrowStatus::InvalidConfig.code();
+const ERR_INVALID_PRIORITY: c_int = PraborrowStatus::InvalidPriority.code();
+const ERR_BUFFER_TOO_SMALL: c_int = PraborrowStatus::BufferTooSmall.code();
+const ERR_INVALID_HANDLE: c_int = PraborrowStatus::InvalidHandle.code();
+const ERR_CONTAINS_NUL: c_int = PraborrowStatus::ContainsNul.code();
+const ERR_INIT_FAILED: c_int = PraborrowStatus::InitFailed.code();
 
 /// Trait for types that can be exchanged across the FFI boundary.
 pub trait Diplomat: serde::Serialize + serde::de::DeserializeOwned {}
@@ -71,46 +104,424 @@ pub(crate) const MAX_QUEUE_DEPTH: usize = 10_000;
 /// Global registry for diplomatic state.
 pub(crate) struct GlobalRegistry {
     /// Envoys received from the foreign jurisdiction, waiting to be processed by Rust.
-    pub(crate) incoming_envoys: SegQueue<String>,
-    pub(crate) incoming_count: AtomicUsize,
+    pub(crate) incoming: Mailbox,
     /// Envoys waiting to be sent to the foreign jurisdiction (outbox).
-    pub(crate) outbox: SegQueue<String>,
-    pub(crate) outbox_count: AtomicUsize,
-    /// Tracks active pointers given to C to prevent double-free.
-    pub(crate) active_loans: DashSet<usize>,
+    pub(crate) outbox: Mailbox,
+    /// Sequence number for the next envelope queued in either direction.
+    pub(crate) next_sequence: AtomicU64,
+    /// Tracks pointers and handles given to C to prevent double-free.
+    pub(crate) loans: LoanLedger,
+    /// Set once relations are severed; closed registries reject new envoys.
+    pub(crate) closed: AtomicBool,
+    /// Decides the reply, if any, to each envoy from the foreign jurisdiction.
+    pub(crate) reply_handler: RwLock<Option<Arc<dyn ReplyHandler>>>,
+    /// Foreign callback that takes outbox envoys as they are sent.
+    pub(crate) envoy_callback: CallbackSlot,
+    /// Takes envoys that overflow a queue configured to spill.
+    pub(crate) spill_handler: RwLock<Option<Arc<dyn SpillHandler>>>,
+    /// Named channels opened on this context, each with its own queues.
+    pub(crate) channels: DashMap<String, Arc<GlobalRegistry>>,
+    /// Calls from the foreign jurisdiction waiting to be served by Rust.
+    pub(crate) calls: Mailbox,
+    /// Reply slots of calls in progress, keyed by correlation id.
+    pub(crate) pending_calls: DashMap<u64, rpc::ReplySlot>,
+    /// Told about envoys that expire in any of the mailboxes above.
+    pub(crate) expiry: Arc<ExpiryHooks>,
+    /// How payloads appear in debug logs.
+    pub(crate) payload_log: PayloadLogConfig,
 }
 
 impl GlobalRegistry {
     pub(crate) fn new() -> Self {
+        Self::with_config(DiplomacyConfig::default())
+    }
+
+    pub(crate) fn with_config(config: DiplomacyConfig) -> Self {
+        let expiry = Arc::new(ExpiryHooks::default());
         Self {
-            incoming_envoys: SegQueue::new(),
-            incoming_count: AtomicUsize::new(0),
-            outbox: SegQueue::new(),
-            outbox_count: AtomicUsize::new(0),
-            active_loans: DashSet::new(),
+            incoming: Mailbox::new(config.incoming, Direction::Incoming, Arc::clone(&expiry)),
+            outbox: Mailbox::new(config.outbox, Direction::Outbox, Arc::clone(&expiry)),
+            next_sequence: AtomicU64::new(1),
+            loans: LoanLedger::new(config.loans),
+            closed: AtomicBool::new(false),
+            reply_handler: RwLock::new(None),
+            envoy_callback: CallbackSlot::new(),
+            spill_handler: RwLock::new(None),
+            channels: DashMap::new(),
+            calls: Mailbox::new(config.incoming, Direction::Incoming, Arc::clone(&expiry)),
+            pending_calls: DashMap::new(),
+            expiry,
+            payload_log: config.payload_log,
+        }
+    }
+
+    pub(crate) fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
+
+    /// Wraps a payload in an envelope stamped with the next sequence number.
+    pub(crate) fn seal(&self, id: u32, routing: Routing, payload: Vec<u8>) -> Envelope {
+        let sequence = self.next_sequence.fetch_add(1, Ordering::Relaxed);
+        Envelope::new(id, sequence, routing, payload)
+    }
+
+    /// Closes the registry and its channels and discards everything still queued.
+    ///
+    /// Receivers blocked on either queue are woken and return empty-handed.
+    /// Outstanding loans are not freed: the foreign side may still be reading
+    /// them. They are reported as leaked instead.
+    pub(crate) fn sever(&self) -> safe::SeveranceReport {
+        self.closed.store(true, Ordering::Release);
+        self.envoy_callback.set(None);
+        self.expiry.set_callback(None);
+
+        self.loans.warn_if_leaked();
+        let mut report = safe::SeveranceReport {
+            discarded_incoming: self.incoming.close(),
+            discarded_outbox: self.outbox.close(),
+            leaked_loans: self.loans.len(),
+        };
+        // Unanswered callers wake with ERR_CLOSED.
+        self.calls.close();
+        self.pending_calls.clear();
+        self.channels.retain(|_, channel| {
+            report.merge(channel.sever());
+            false
+        });
+        report
+    }
+
+    /// Returns the channel called `name`, creating it with `config` if it does
+    /// not exist or was closed.
+    ///
+    /// `config` is ignored when an open channel of that name already exists.
+    pub(crate) fn open_channel(
+        &self,
+        name: &str,
+        config: DiplomacyConfig,
+    ) -> Result<Arc<GlobalRegistry>, safe::DiplomacyError> {
+        config.validate()?;
+        let mut channel = self
+            .channels
+            .entry(name.to_owned())
+            .or_insert_with(|| Arc::new(GlobalRegistry::with_config(config)));
+        // Checked while holding the entry: `sever` marks the registry closed
+        // before sweeping the channels, so either it sees this channel or we
+        // see it closed.
+        if self.is_closed() {
+            drop(channel);
+            self.channels.remove(name);
+            return Err(safe::DiplomacyError::Closed);
+        }
+        if channel.is_closed() {
+            *channel = Arc::new(GlobalRegistry::with_config(config));
+        }
+        Ok(Arc::clone(&channel))
+    }
+
+    /// Installs or removes the handler consulted for every foreign envoy.
+    pub(crate) fn set_reply_handler(&self, handler: Option<Arc<dyn ReplyHandler>>) {
+        *self
+            .reply_handler
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = handler;
+    }
+
+    /// Asks the installed handler for a reply to `envelope`.
+    ///
+    /// A panicking handler is treated as declining to reply, so that the panic
+    /// never unwinds into the foreign caller.
+    pub(crate) fn reply_to(&self, envelope: &Envelope) -> Option<Vec<u8>> {
+        let handler = self
+            .reply_handler
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()?;
+
+        match catch_unwind(AssertUnwindSafe(|| handler.reply(envelope))) {
+            Ok(reply) => reply,
+            Err(_) => {
+                tracing::error!(envoy_id = envelope.id, "Panic caught in reply handler");
+                None
+            }
+        }
+    }
+
+    /// Installs or removes the handler that takes spilled envoys.
+    pub(crate) fn set_spill_handler(&self, handler: Option<Arc<dyn SpillHandler>>) {
+        *self
+            .spill_handler
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = handler;
+    }
+
+    fn has_spill_handler(&self) -> bool {
+        self.spill_handler
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .is_some()
+    }
+
+    /// Reserves room in `direction`'s queue according to its overflow policy.
+    pub(crate) fn admit(
+        &self,
+        direction: Direction,
+        len: usize,
+        priority: Priority,
+    ) -> Result<Admission, safe::DiplomacyError> {
+        let mailbox = match direction {
+            Direction::Incoming => &self.incoming,
+            Direction::Outbox => &self.outbox,
+        };
+        mailbox.admit(len, priority, self.has_spill_handler())
+    }
+
+    /// Hands an envelope that overflowed `direction`'s queue to the spill handler.
+    ///
+    /// The envelope is dropped if the handler was removed after admission or
+    /// panics; either way the panic never unwinds into the sender.
+    pub(crate) fn spill(&self, direction: Direction, envelope: Envelope) {
+        let handler = self
+            .spill_handler
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        let Some(handler) = handler else {
+            tracing::warn!(
+                envoy_id = envelope.id,
+                ?direction,
+                "Envoy dropped: no spill handler"
+            );
+            return;
+        };
+
+        let id = envelope.id;
+        if catch_unwind(AssertUnwindSafe(|| handler.spill(direction, envelope))).is_err() {
+            tracing::error!(envoy_id = id, ?direction, "Panic caught in spill handler");
+        }
+    }
+
+    /// Overflow counters of both queues.
+    pub(crate) fn overflow_report(&self) -> OverflowReport {
+        OverflowReport {
+            incoming: self.incoming.overflow_counts(),
+            outbox: self.outbox.overflow_counts(),
+        }
+    }
+
+    /// Traffic of both queues, loans and failed calls.
+    pub(crate) fn stats(&self) -> DiplomacyStats {
+        DiplomacyStats {
+            incoming: self.incoming.stats(),
+            outbox: self.outbox.stats(),
+            outstanding_loans: self.loans.len(),
+            double_frees: self.loans.double_frees(),
+            rejects: stats::rejects(),
+        }
+    }
+
+    /// Queues a message for the foreign jurisdiction.
+    pub(crate) fn dispatch(
+        &self,
+        id: u32,
+        routing: Routing,
+        payload: &[u8],
+    ) -> Result<(), safe::DiplomacyError> {
+        if self.is_closed() {
+            return Err(safe::DiplomacyError::Closed);
+        }
+
+        tracing::debug!(
+            event = "envoy_dispatched",
+            envoy_id = id,
+            len = payload.len(),
+            payload = logging::payload(self.payload_log, payload),
+            "Envoy dispatched to foreign jurisdiction"
+        );
+
+        // Push-style delivery bypasses the outbox entirely.
+        let envelope = self.seal(id, routing, payload.to_vec());
+        let Some(envelope) = self.envoy_callback.deliver(envelope) else {
+            self.outbox.count_delivered();
+            return Ok(());
+        };
+
+        // OOM Check
+        match self.admit(Direction::Outbox, envelope.payload.len(), routing.priority)? {
+            Admission::Queue => self.outbox.push(envelope),
+            Admission::Spill => self.spill(Direction::Outbox, envelope),
+        }
+        Ok(())
+    }
+
+    /// Installs or removes the foreign envoy callback.
+    ///
+    /// Envoys already waiting in the outbox are flushed to a newly installed
+    /// callback so that nothing is left behind for a poller that no longer polls.
+    pub(crate) fn set_envoy_callback(&self, callback: Option<EnvoyCallback>) {
+        self.envoy_callback.set(callback);
+        if callback.is_none() {
+            return;
+        }
+
+        while let Some(envelope) = self.outbox.pop() {
+            if let Some(envelope) = self.envoy_callback.deliver(envelope) {
+                // Unregistered concurrently: keep the envoy for polling.
+                self.outbox.requeue(envelope);
+                break;
+            }
+        }
+    }
+
+    /// Takes the next message sent by the foreign jurisdiction, if any.
+    pub(crate) fn take_incoming(&self) -> Option<Envelope> {
+        self.incoming
+            .pop()
+            .inspect(|envelope| self.log_taken(envelope))
+    }
+
+    /// Takes up to `max` messages sent by the foreign jurisdiction without blocking.
+    pub(crate) fn drain_incoming(&self, max: usize) -> Vec<Envelope> {
+        std::iter::from_fn(|| self.take_incoming())
+            .take(max)
+            .collect()
+    }
+
+    /// Logs that Rust took an envoy sent by the foreign jurisdiction.
+    fn log_taken(&self, envelope: &Envelope) {
+        tracing::debug!(
+            event = "envoy_taken",
+            envoy_id = envelope.id,
+            sequence = envelope.sequence,
+            len = envelope.payload.len(),
+            payload = logging::payload(self.payload_log, &envelope.payload),
+            "Envoy taken by Rust"
+        );
+    }
+
+    /// Queues messages for the foreign jurisdiction in order, stopping at the
+    /// first one that fails. Returns how many were queued.
+    pub(crate) fn dispatch_batch<I, P>(&self, envoys: I) -> Result<usize, safe::BatchError>
+    where
+        I: IntoIterator<Item = (u32, P)>,
+        P: AsRef<[u8]>,
+    {
+        let mut sent = 0;
+        for (id, payload) in envoys {
+            self.dispatch(id, Routing::default(), payload.as_ref())
+                .map_err(|error| safe::BatchError { sent, error })?;
+            sent += 1;
         }
+        Ok(sent)
+    }
+
+    /// Takes the next message sent by the foreign jurisdiction, waiting for
+    /// one to arrive until `deadline` (or indefinitely if `None`).
+    pub(crate) fn wait_incoming(
+        &self,
+        deadline: Option<Instant>,
+    ) -> Result<Envelope, safe::DiplomacyError> {
+        match self.incoming.pop_wait(deadline) {
+            Some(envelope) => {
+                self.log_taken(&envelope);
+                Ok(envelope)
+            }
+            None if self.incoming.is_closed() => Err(safe::DiplomacyError::Closed),
+            None => Err(safe::DiplomacyError::Timeout),
+        }
+    }
+}
+
+/// The current diplomatic session, if relations are established.
+///
+/// Held behind a lock rather than a `OnceLock` so that a session can be
+/// severed and a new one established later in the same process.
+pub(crate) static REGISTRY: RwLock<Option<Arc<GlobalRegistry>>> = RwLock::new(None);
+
+/// Returns the registry of the current session, if any.
+pub(crate) fn current_registry() -> Option<Arc<GlobalRegistry>> {
+    REGISTRY
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
+/// Starts a new session, failing if one is already active.
+pub(crate) fn install_registry(config: DiplomacyConfig) -> Result<(), safe::DiplomacyError> {
+    config.validate()?;
+    let mut slot = REGISTRY
+        .write()
+        .map_err(|_| safe::DiplomacyError::InitFailed)?;
+    if slot.is_some() {
+        return Err(safe::DiplomacyError::AlreadyInitialized);
     }
+    *slot = Some(Arc::new(GlobalRegistry::with_config(config)));
+    Ok(())
 }
 
-pub(crate) static REGISTRY: OnceLock<GlobalRegistry> = OnceLock::new();
+/// Ends the current session and returns what was discarded.
+///
+/// Drops the registry, which closes its readiness descriptors.
+pub(crate) fn uninstall_registry() -> Result<safe::SeveranceReport, safe::DiplomacyError> {
+    let registry = REGISTRY
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .take()
+        .ok_or(safe::DiplomacyError::NotInitialized)?;
+    Ok(registry.sever())
+}
 
 /// Establishes diplomatic relations with the PraBorrow runtime.
 ///
-/// Initializes the global registry.
+/// Initializes the global registry with the default configuration. Relations
+/// can be established again after `sever_relations`.
 ///
 /// # Returns
 /// * `0` - Success
-/// * `-1` - Already initialized
-/// * `-2` - Initialization failed
+/// * `-1` - Already initialized, and not severed since
+/// * `-2` - Initialization failed: a panic poisoned the session lock
 #[unsafe(no_mangle)]
 #[tracing::instrument]
 pub extern "C" fn establish_relations() -> c_int {
-    if REGISTRY.get().is_some() {
-        return ERR_ALREADY_INIT;
+    establish_relations_in("establish_relations", DiplomacyConfig::default())
+}
+
+/// Returns the default configuration, for C callers to adjust before
+/// passing it to `establish_relations_with_config`.
+#[unsafe(no_mangle)]
+pub extern "C" fn praborrow_config_default() -> DiplomacyConfig {
+    DiplomacyConfig::default()
+}
+
+/// Establishes diplomatic relations with custom queue limits.
+///
+/// # Returns
+/// * `0` - Success
+/// * `-1` - Already initialized, and not severed since
+/// * `-2` - Initialization failed: a panic poisoned the session lock
+/// * `-3` - `config` is NULL
+/// * `-10` - A limit in `config` is zero or a policy is unknown
+///
+/// # Safety
+/// * `config` must be NULL or point to a valid `praborrow_config`.
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(config))]
+pub unsafe extern "C" fn establish_relations_with_config(config: *const DiplomacyConfig) -> c_int {
+    match unsafe { config.as_ref() } {
+        Some(config) => establish_relations_in("establish_relations_with_config", *config),
+        None => last_error::fail(
+            ERR_NULL_PTR,
+            "establish_relations_with_config",
+            None,
+            "config is NULL",
+        ),
     }
+}
 
-    match REGISTRY.set(GlobalRegistry::new()) {
-        Ok(_) => {
+/// Shared implementation of `establish_relations` and `establish_relations_with_config`.
+fn establish_relations_in(function: &'static str, config: DiplomacyConfig) -> c_int {
+    match install_registry(config) {
+        Ok(()) => {
             tracing::info!(
                 event = "ffi_init",
                 version = env!("CARGO_PKG_VERSION"),
@@ -118,17 +529,27 @@ pub extern "C" fn establish_relations() -> c_int {
             );
             SUCCESS
         }
-        Err(_) => {
+        Err(safe::DiplomacyError::AlreadyInitialized) => last_error::fail(
+            ERR_ALREADY_INIT,
+            function,
+            None,
+            "relations are already established",
+        ),
+        Err(safe::DiplomacyError::InvalidConfig) => {
+            tracing::error!(?config, "Rejected invalid configuration");
+            last_error::fail(
+                ERR_INVALID_CONFIG,
+                function,
+                None,
+                "a queue limit is zero, or an overflow policy or payload log mode is unknown",
+            )
+        }
+        Err(e) => {
             tracing::error!("Failed to initialize GlobalRegistry");
-            ERR_INIT_FAILED
+            last_error::fail(ERR_INIT_FAILED, function, None, e)
         }
     }
 }
-// Note: establish_relations logic has a bug in original code:
-// if REGISTRY.get().is_some() returns -1 (AlreadyInit).
-// REGISTRY.set() returns Err if already set.
-// So we should return ERR_ALREADY_INIT in the check, and ERR_INIT_FAILED if logic fails otherwise (unlikely for OnceLock).
-// I will keep the check pattern but use constants.
 
 /// Alternative name for `establish_relations`.
 #[unsafe(no_mangle)]
@@ -136,16 +557,278 @@ pub extern "C" fn init_ffi() -> c_int {
     establish_relations()
 }
 
+/// Severs diplomatic relations, ending the current session.
+///
+/// Discards every envoy still queued in either direction and closes the
+/// registry so that in-flight `send_envoy` calls are rejected. Pointers still
+/// on loan to the foreign side stay valid but can no longer be returned with
+/// `free_envoy`; they are reported as leaked. The descriptor returned by
+/// `praborrow_outbox_fd` is closed, so unregister it first. A later
+/// `establish_relations` starts a fresh session.
+///
+/// # Returns
+/// * `>= 0` - Success, number of leaked loans
+/// * `-15` - Registry not initialized
+#[unsafe(no_mangle)]
+#[tracing::instrument]
+pub extern "C" fn sever_relations() -> c_int {
+    match uninstall_registry() {
+        Ok(report) => {
+            if report.leaked_loans > 0 {
+                tracing::warn!(
+                    event = "ffi_loans_leaked",
+                    leaked_loans = report.leaked_loans,
+                    "Relations severed with envoys still on loan"
+                );
+            }
+            tracing::info!(
+                event = "ffi_shutdown",
+                discarded_incoming = report.discarded_incoming,
+                discarded_outbox = report.discarded_outbox,
+                "Diplomatic relations severed"
+            );
+            c_int::try_from(report.leaked_loans).unwrap_or(c_int::MAX)
+        }
+        Err(_) => not_established("sever_relations"),
+    }
+}
+
+/// Opaque handle to an independent diplomatic context.
+///
+/// Each context owns its own inbox, outbox and loan table, so components that
+/// share a process no longer share a stream. Created by
+/// `praborrow_context_new` and released by `praborrow_context_free`.
+pub struct Context {
+    registry: Arc<GlobalRegistry>,
+}
+
+impl Context {
+    pub(crate) fn new(registry: Arc<GlobalRegistry>) -> Self {
+        Self { registry }
+    }
+
+    pub(crate) fn registry(&self) -> &Arc<GlobalRegistry> {
+        &self.registry
+    }
+
+    /// Borrows the context behind a foreign pointer.
+    ///
+    /// # Safety
+    /// `ctx` must be null or a live pointer returned by `praborrow_context_new`.
+    pub(crate) unsafe fn from_ptr<'a>(ctx: *const Context) -> Option<&'a Context> {
+        unsafe { ctx.as_ref() }
+    }
+}
+
+/// Creates a new, independent diplomatic context.
+///
+/// The context is ready for use immediately; it does not need
+/// `establish_relations`.
+///
+/// # Returns
+/// * `praborrow_context*` - Owned handle, release with `praborrow_context_free`.
+#[unsafe(no_mangle)]
+#[tracing::instrument]
+pub extern "C" fn praborrow_context_new() -> *mut Context {
+    let ctx = Box::new(Context::new(Arc::new(GlobalRegistry::new())));
+    tracing::info!(event = "ffi_context_new", "Diplomatic context created");
+    Box::into_raw(ctx)
+}
+
+/// Creates a new, independent diplomatic context with custom queue limits.
+///
+/// # Returns
+/// * `praborrow_context*` - Owned handle, release with `praborrow_context_free`.
+/// * `NULL` - `config` is NULL or contains a zero limit.
+///
+/// # Safety
+/// * `config` must be NULL or point to a valid `praborrow_config`.
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(config))]
+pub unsafe extern "C" fn praborrow_context_new_with_config(
+    config: *const DiplomacyConfig,
+) -> *mut Context {
+    let Some(config) = (unsafe { config.as_ref() }) else {
+        return std::ptr::null_mut();
+    };
+    if config.validate().is_err() {
+        tracing::error!(?config, "Rejected invalid configuration");
+        return std::ptr::null_mut();
+    }
+
+    let ctx = Box::new(Context::new(Arc::new(GlobalRegistry::with_config(*config))));
+    tracing::info!(event = "ffi_context_new", "Diplomatic context created");
+    Box::into_raw(ctx)
+}
+
+/// Severs a context and releases its handle.
+///
+/// Behaves like `sever_relations` for the given context: queued envoys are
+/// discarded and loans still outstanding are reported as leaked.
+///
+/// # Returns
+/// * `>= 0` - Success, number of leaked loans
+/// * `-3` - `ctx` is NULL
+///
+/// # Safety
+/// * `ctx` must be a pointer returned by `praborrow_context_new`.
+/// * Must not be called more than once for the same pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn praborrow_context_free(ctx: *mut Context) -> c_int {
+    if ctx.is_null() {
+        return null_context("praborrow_context_free");
+    }
+
+    let ctx = unsafe { Box::from_raw(ctx) };
+    let report = ctx.registry.sever();
+    if report.leaked_loans > 0 {
+        tracing::warn!(
+            event = "ffi_loans_leaked",
+            leaked_loans = report.leaked_loans,
+            "Context freed with envoys still on loan"
+        );
+    }
+    c_int::try_from(report.leaked_loans).unwrap_or(c_int::MAX)
+}
+
+/// Opens the channel called `name` on the default context.
+///
+/// A channel is a context of its own, with separate queues, limits, handlers
+/// and counters, so the returned handle works with every `*_ctx` function.
+/// Opening an existing name returns another handle to the same channel.
+/// Severing the default context closes all of its channels.
+///
+/// # Returns
+/// * `praborrow_context*` - Owned handle, release with `praborrow_context_free`,
+///   which closes the channel for every holder; it is recreated empty on next open.
+/// * `NULL` - Registry not initialized, or `name` is NULL or not UTF-8.
+///
+/// # Safety
+/// * `name` must be NULL or a valid pointer to a null-terminated C string.
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(name))]
+pub unsafe extern "C" fn praborrow_channel_open(name: *const c_char) -> *mut Context {
+    match current_registry() {
+        Some(registry) => unsafe { channel_open_in(&registry, name, DiplomacyConfig::default()) },
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// Opens the channel called `name` on the default context, creating it with
+/// custom queue limits.
+///
+/// `config` only applies if the channel does not exist yet.
+///
+/// # Returns
+/// * `NULL` - Additionally, `config` is NULL or invalid.
+/// * Otherwise as `praborrow_channel_open`
+///
+/// # Safety
+/// * `name` must be NULL or a valid pointer to a null-terminated C string.
+/// * `config` must be NULL or point to a valid `praborrow_config`.
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(name, config))]
+pub unsafe extern "C" fn praborrow_channel_open_with_config(
+    name: *const c_char,
+    config: *const DiplomacyConfig,
+) -> *mut Context {
+    match (current_registry(), unsafe { config.as_ref() }) {
+        (Some(registry), Some(config)) => unsafe { channel_open_in(&registry, name, *config) },
+        _ => std::ptr::null_mut(),
+    }
+}
+
+/// Opens the channel called `name` on `ctx`.
+///
+/// # Returns
+/// As `praborrow_channel_open`; `NULL` also if `ctx` is NULL.
+///
+/// # Safety
+/// * `ctx` must be NULL or a live pointer returned by `praborrow_context_new`.
+/// * `name` must be NULL or a valid pointer to a null-terminated C string.
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(ctx, name))]
+pub unsafe extern "C" fn praborrow_channel_open_ctx(
+    ctx: *const Context,
+    name: *const c_char,
+) -> *mut Context {
+    match unsafe { Context::from_ptr(ctx) } {
+        Some(ctx) => unsafe { channel_open_in(ctx.registry(), name, DiplomacyConfig::default()) },
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// Opens the channel called `name` on `ctx`, creating it with custom queue limits.
+///
+/// # Returns
+/// As `praborrow_channel_open_with_config`; `NULL` also if `ctx` is NULL.
+///
+/// # Safety
+/// * `ctx` must be NULL or a live pointer returned by `praborrow_context_new`.
+/// * `name` must be NULL or a valid pointer to a null-terminated C string.
+/// * `config` must be NULL or point to a valid `praborrow_config`.
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(ctx, name, config))]
+pub unsafe extern "C" fn praborrow_channel_open_with_config_ctx(
+    ctx: *const Context,
+    name: *const c_char,
+    config: *const DiplomacyConfig,
+) -> *mut Context {
+    match (unsafe { Context::from_ptr(ctx) }, unsafe {
+        config.as_ref()
+    }) {
+        (Some(ctx), Some(config)) => unsafe { channel_open_in(ctx.registry(), name, *config) },
+        _ => std::ptr::null_mut(),
+    }
+}
+
+/// Shared implementation of the `praborrow_channel_open*` functions.
+///
+/// # Safety
+/// * `name` must be NULL or a valid pointer to a null-terminated C string.
+unsafe fn channel_open_in(
+    registry: &GlobalRegistry,
+    name: *const c_char,
+    config: DiplomacyConfig,
+) -> *mut Context {
+    if name.is_null() {
+        tracing::error!("Received NULL channel name");
+        return std::ptr::null_mut();
+    }
+    let Ok(name) = unsafe { CStr::from_ptr(name) }.to_str() else {
+        tracing::error!("Invalid UTF-8 in channel name");
+        return std::ptr::null_mut();
+    };
+
+    match registry.open_channel(name, config) {
+        Ok(channel) => {
+            tracing::debug!(event = "ffi_channel_open", channel = name, "Channel opened");
+            Box::into_raw(Box::new(Context::new(channel)))
+        }
+        Err(e) => {
+            tracing::error!(channel = name, error = %e, "Failed to open channel");
+            std::ptr::null_mut()
+        }
+    }
+}
+
 /// Sends an envoy (notification) FROM the foreign jurisdiction TO Rust.
 ///
+/// Operates on the default context set up by `establish_relations`.
+///
 /// # Arguments
 /// * `id` - Unique identifier
 /// * `payload` - Null-terminated C string message
 ///
 /// # Returns
 /// * `0` - Success
-/// * `-1` - Registry not initialized
-/// * `-2` - Invalid string encoding
+/// * `-3` - `payload` is NULL
+/// * `-4` - `payload` is not valid UTF-8
+/// * `-5` - `id` is 0
+/// * `-6` - Panic while reading `payload`
+/// * `-7` - Queue capacity exceeded
+/// * `-8` - Relations were severed during the call
+/// * `-15` - Registry not initialized
 ///
 /// # Safety
 ///
@@ -154,20 +837,66 @@ pub extern "C" fn init_ffi() -> c_int {
 #[unsafe(no_mangle)]
 #[tracing::instrument(skip(payload))]
 pub unsafe extern "C" fn send_envoy(id: u32, payload: *const c_char) -> c_int {
-    let registry = match REGISTRY.get() {
+    let registry = match current_registry() {
         Some(r) => r,
-        None => return ERR_INIT_FAILED,
+        None => return not_established("send_envoy"),
     };
-    // ...
 
+    unsafe { send_envoy_in(&registry, "send_envoy", id, Routing::default(), payload) }
+}
+
+/// Sends an envoy FROM the foreign jurisdiction TO Rust on a specific context.
+///
+/// Same as `send_envoy`, but targets `ctx` instead of the default context.
+///
+/// # Returns
+/// * `-3` - `ctx` or `payload` is NULL
+/// * Otherwise as `send_envoy`
+///
+/// # Safety
+///
+/// * `ctx` must be a live pointer returned by `praborrow_context_new`.
+/// * `payload` must be a valid pointer to a null-terminated C string.
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(ctx, payload))]
+pub unsafe extern "C" fn send_envoy_ctx(
+    ctx: *const Context,
+    id: u32,
+    payload: *const c_char,
+) -> c_int {
+    match unsafe { Context::from_ptr(ctx) } {
+        Some(ctx) => unsafe {
+            send_envoy_in(
+                ctx.registry(),
+                "send_envoy_ctx",
+                id,
+                Routing::default(),
+                payload,
+            )
+        },
+        None => null_context("send_envoy_ctx"),
+    }
+}
+
+/// Shared implementation of `send_envoy` and `send_envoy_ctx`.
+///
+/// # Safety
+/// See `send_envoy`.
+unsafe fn send_envoy_in(
+    registry: &GlobalRegistry,
+    function: &'static str,
+    id: u32,
+    routing: Routing,
+    payload: *const c_char,
+) -> c_int {
     if payload.is_null() {
         tracing::error!(envoy_id = id, "Received NULL payload");
-        return ERR_NULL_PTR;
+        return last_error::fail(ERR_NULL_PTR, function, Some(id), "payload is NULL");
     }
 
     if id == 0 {
         tracing::error!(envoy_id = 0, "Received Invalid ID 0");
-        return ERR_INVALID_ID;
+        return last_error::fail(ERR_INVALID_ID, function, Some(id), "id 0 is reserved");
     }
 
     // Wrap unsafe dereference and string conversion in catch_unwind
@@ -179,169 +908,3697 @@ pub unsafe extern "C" fn send_envoy(id: u32, payload: *const c_char) -> c_int {
 
     let r_str = match r_str_result {
         Ok(Ok(s)) => s,
-        Ok(Err(_)) => {
-            tracing::error!(envoy_id = id, "Invalid UTF-8 in payload");
-            return ERR_INVALID_UTF8;
+        Ok(Err(e)) => {
+            tracing::error!(
+                envoy_id = id,
+                offset = e.valid_up_to(),
+                "Invalid UTF-8 in payload"
+            );
+            return last_error::fail(
+                ERR_INVALID_UTF8,
+                function,
+                Some(id),
+                format_args!("invalid UTF-8 at byte {}", e.valid_up_to()),
+            );
         } // UTF-8 error
         Err(_) => {
             tracing::error!(envoy_id = id, "Panic caught across FFI boundary");
-            return ERR_PANIC;
+            return last_error::fail(
+                ERR_PANIC,
+                function,
+                Some(id),
+                "panic while reading the payload",
+            );
         } // Panic occurred
     };
 
     tracing::debug!(
         event = "envoy_received",
         envoy_id = id,
-        payload = ?r_str,
+        len = r_str.len(),
+        payload = logging::payload(registry.payload_log, r_str.as_bytes()),
         "Envoy received from foreign jurisdiction"
     );
 
-    // OOM Prevention: Check Limits
-    // 1. Check Incoming Queue
-    if registry.incoming_count.load(Ordering::Relaxed) >= MAX_QUEUE_DEPTH {
-        return ERR_QUEUE_FULL;
-    }
-    // 2. Check Outbox (for Echo)
-    if registry.outbox_count.load(Ordering::Relaxed) >= MAX_QUEUE_DEPTH {
-        // Technically this is a partial failure if incoming succeeded but echo failed.
-        // For simplicity, we fail the whole operation to apply backpressure.
-        return ERR_QUEUE_FULL;
-    }
-
-    // Commit to queues
-    // Note: Race condition exists between load and push, but precise count isn't critical for safety, just bounding.
-    // Using fetch_add would be more precise.
-    let old_incoming = registry.incoming_count.fetch_add(1, Ordering::Relaxed);
-    if old_incoming >= MAX_QUEUE_DEPTH {
-        registry.incoming_count.fetch_sub(1, Ordering::Relaxed);
-        return ERR_QUEUE_FULL;
-    }
-
-    registry
-        .incoming_envoys
-        .push(format!("ID:{}:{}", id, r_str));
-
-    let old_outbox = registry.outbox_count.fetch_add(1, Ordering::Relaxed);
-    if old_outbox >= MAX_QUEUE_DEPTH {
-        registry.outbox_count.fetch_sub(1, Ordering::Relaxed);
-        // Note: We already pushed to incoming, so this is an edge case.
-        // But prevents outbox OOM.
-        return ERR_QUEUE_FULL;
-    }
-
-    // Auto-reply for testing
-    registry.outbox.push(format!("Ack: {}", r_str));
-
-    SUCCESS
+    deliver_envoy(registry, function, id, routing, r_str.into_bytes())
 }
 
-/// Receives an envoy (message) FROM Rust TO the foreign jurisdiction.
+/// Sends a length-delimited binary envoy FROM the foreign jurisdiction TO Rust.
 ///
-/// Pops a message from the internal outbox.
+/// Unlike `send_envoy`, the payload may contain NUL bytes and need not be UTF-8.
+///
+/// # Arguments
+/// * `id` - Unique identifier
+/// * `data` - Payload bytes; may be NULL when `len` is 0
+/// * `len` - Number of bytes at `data`
 ///
 /// # Returns
-/// * `char*` - Pointer to null-terminated string. Ownership transferred to caller.
-/// * `NULL` - No messages available or error.
+/// * `0` - Success
+/// * `-3` - `data` is NULL with a non-zero `len`
+/// * `-5` - `id` is 0
+/// * `-7` - Queue capacity exceeded
+/// * `-8` - Relations were severed during the call
+/// * `-15` - Registry not initialized
+///
+/// # Safety
+///
+/// * `data` must be valid for reads of `len` bytes for the duration of the call.
 #[unsafe(no_mangle)]
-#[tracing::instrument]
-pub extern "C" fn receive_envoy() -> *mut c_char {
-    let registry = match REGISTRY.get() {
+#[tracing::instrument(skip(data))]
+pub unsafe extern "C" fn send_envoy_bytes(id: u32, data: *const u8, len: usize) -> c_int {
+    let registry = match current_registry() {
         Some(r) => r,
-        None => return std::ptr::null_mut(),
+        None => return not_established("send_envoy_bytes"),
     };
 
-    let msg = registry.outbox.pop();
-
-    match msg {
-        Some(s) => {
-            // Decrement count
-            registry.outbox_count.fetch_sub(1, Ordering::Relaxed);
-
-            match CString::new(s) {
-                Ok(c_str) => {
-                    let ptr = c_str.into_raw();
-                    // Register the pointer as active
-                    registry.active_loans.insert(ptr as usize);
-                    ptr
-                }
-                Err(_) => std::ptr::null_mut(),
-            }
-        }
-        None => std::ptr::null_mut(),
+    unsafe {
+        send_envoy_bytes_in(
+            &registry,
+            "send_envoy_bytes",
+            id,
+            Routing::default(),
+            data,
+            len,
+        )
     }
 }
 
-/// Frees a string returned by `receive_envoy`.
+/// Sends a length-delimited binary envoy on a specific context.
+///
+/// # Returns
+/// * `-3` - `ctx` is NULL, or `data` is NULL with a non-zero `len`
+/// * Otherwise as `send_envoy_bytes`
 ///
 /// # Safety
-/// * `envoy` must be a pointer returned by `receive_envoy`.
-/// * Must not be called more than once for the same pointer.
+///
+/// * `ctx` must be a live pointer returned by `praborrow_context_new`.
+/// * `data` must be valid for reads of `len` bytes for the duration of the call.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn free_envoy(envoy: *mut c_char) {
-    if envoy.is_null() {
-        return;
+#[tracing::instrument(skip(ctx, data))]
+pub unsafe extern "C" fn send_envoy_bytes_ctx(
+    ctx: *const Context,
+    id: u32,
+    data: *const u8,
+    len: usize,
+) -> c_int {
+    match unsafe { Context::from_ptr(ctx) } {
+        Some(ctx) => unsafe {
+            send_envoy_bytes_in(
+                ctx.registry(),
+                "send_envoy_bytes_ctx",
+                id,
+                Routing::default(),
+                data,
+                len,
+            )
+        },
+        None => null_context("send_envoy_bytes_ctx"),
     }
+}
 
-    let registry = match REGISTRY.get() {
-        Some(r) => r,
-        None => return, // Should not happen if we gave out a pointer
+/// Shared implementation of `send_envoy_bytes` and `send_envoy_bytes_ctx`.
+///
+/// # Safety
+/// See `send_envoy_bytes`.
+unsafe fn send_envoy_bytes_in(
+    registry: &GlobalRegistry,
+    function: &'static str,
+    id: u32,
+    routing: Routing,
+    data: *const u8,
+    len: usize,
+) -> c_int {
+    if data.is_null() && len != 0 {
+        tracing::error!(envoy_id = id, len, "Received NULL payload");
+        return last_error::fail(
+            ERR_NULL_PTR,
+            function,
+            Some(id),
+            format_args!("data is NULL with len {len}"),
+        );
+    }
+
+    if id == 0 {
+        tracing::error!(envoy_id = 0, "Received Invalid ID 0");
+        return last_error::fail(ERR_INVALID_ID, function, Some(id), "id 0 is reserved");
+    }
+
+    let bytes_result = catch_unwind(|| {
+        if len == 0 {
+            Vec::new()
+        } else {
+            unsafe { std::slice::from_raw_parts(data, len) }.to_vec()
+        }
+    });
+
+    let bytes = match bytes_result {
+        Ok(bytes) => bytes,
+        Err(_) => {
+            tracing::error!(envoy_id = id, "Panic caught across FFI boundary");
+            return last_error::fail(
+                ERR_PANIC,
+                function,
+                Some(id),
+                "panic while reading the payload",
+            );
+        }
     };
 
-    let ptr_val = envoy as usize;
+    tracing::debug!(
+        event = "envoy_received",
+        envoy_id = id,
+        len = bytes.len(),
+        payload = logging::payload(registry.payload_log, &bytes),
+        "Binary envoy received from foreign jurisdiction"
+    );
 
-    // Check if we actually loaned this pointer
-    if registry.active_loans.remove(&ptr_val).is_some() {
-        // Safe to free: we created it and haven't freed it yet
-        // Retake ownership to drop it
-        unsafe {
-            let _ = CString::from_raw(envoy);
+    deliver_envoy(registry, function, id, routing, bytes)
+}
+
+/// Sends an envoy FROM the foreign jurisdiction TO Rust in a priority lane.
+///
+/// Rust receives envoys of a higher priority first, while lower lanes are
+/// still served now and then so they cannot starve.
+///
+/// # Arguments
+/// * `priority` - `0` (low), `1` (normal, as `send_envoy`) or `2` (high)
+///
+/// # Returns
+/// * `-12` - `priority` names no lane
+/// * Otherwise as `send_envoy`
+///
+/// # Safety
+/// See `send_envoy`.
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(payload))]
+pub unsafe extern "C" fn send_envoy_priority(
+    id: u32,
+    priority: u8,
+    payload: *const c_char,
+) -> c_int {
+    let Some(registry) = current_registry() else {
+        return not_established("send_envoy_priority");
+    };
+    match Priority::try_from(priority) {
+        Ok(priority) => unsafe {
+            send_envoy_in(
+                &registry,
+                "send_envoy_priority",
+                id,
+                Routing::with_priority(priority),
+                payload,
+            )
+        },
+        Err(priority) => invalid_priority("send_envoy_priority", id, priority),
+    }
+}
+
+/// Sends an envoy in a priority lane on a specific context.
+///
+/// # Returns
+/// * `-3` - `ctx` or `payload` is NULL
+/// * Otherwise as `send_envoy_priority`
+///
+/// # Safety
+/// * `ctx` must be a live pointer returned by `praborrow_context_new`.
+/// * Otherwise as `send_envoy`
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(ctx, payload))]
+pub unsafe extern "C" fn send_envoy_priority_ctx(
+    ctx: *const Context,
+    id: u32,
+    priority: u8,
+    payload: *const c_char,
+) -> c_int {
+    let Some(ctx) = (unsafe { Context::from_ptr(ctx) }) else {
+        return null_context("send_envoy_priority_ctx");
+    };
+    match Priority::try_from(priority) {
+        Ok(priority) => unsafe {
+            send_envoy_in(
+                ctx.registry(),
+                "send_envoy_priority_ctx",
+                id,
+                Routing::with_priority(priority),
+                payload,
+            )
+        },
+        Err(priority) => invalid_priority("send_envoy_priority_ctx", id, priority),
+    }
+}
+
+/// Sends a length-delimited binary envoy in a priority lane.
+///
+/// # Returns
+/// * `-12` - `priority` names no lane
+/// * Otherwise as `send_envoy_bytes`
+///
+/// # Safety
+/// See `send_envoy_bytes`.
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(data))]
+pub unsafe extern "C" fn send_envoy_bytes_priority(
+    id: u32,
+    priority: u8,
+    data: *const u8,
+    len: usize,
+) -> c_int {
+    let Some(registry) = current_registry() else {
+        return not_established("send_envoy_bytes_priority");
+    };
+    match Priority::try_from(priority) {
+        Ok(priority) => unsafe {
+            send_envoy_bytes_in(
+                &registry,
+                "send_envoy_bytes_priority",
+                id,
+                Routing::with_priority(priority),
+                data,
+                len,
+            )
+        },
+        Err(priority) => invalid_priority("send_envoy_bytes_priority", id, priority),
+    }
+}
+
+/// Sends a length-delimited binary envoy in a priority lane on a specific context.
+///
+/// # Returns
+/// * `-3` - `ctx` is NULL, or `data` is NULL with a non-zero `len`
+/// * Otherwise as `send_envoy_bytes_priority`
+///
+/// # Safety
+/// * `ctx` must be a live pointer returned by `praborrow_context_new`.
+/// * Otherwise as `send_envoy_bytes`
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(ctx, data))]
+pub unsafe extern "C" fn send_envoy_bytes_priority_ctx(
+    ctx: *const Context,
+    id: u32,
+    priority: u8,
+    data: *const u8,
+    len: usize,
+) -> c_int {
+    let Some(ctx) = (unsafe { Context::from_ptr(ctx) }) else {
+        return null_context("send_envoy_bytes_priority_ctx");
+    };
+    match Priority::try_from(priority) {
+        Ok(priority) => unsafe {
+            send_envoy_bytes_in(
+                ctx.registry(),
+                "send_envoy_bytes_priority_ctx",
+                id,
+                Routing::with_priority(priority),
+                data,
+                len,
+            )
+        },
+        Err(priority) => invalid_priority("send_envoy_bytes_priority_ctx", id, priority),
+    }
+}
+
+/// Returns the options of a plain `send_envoy`, for C callers to adjust
+/// before passing them to `send_envoy_with_options`.
+#[unsafe(no_mangle)]
+pub extern "C" fn praborrow_send_options_default() -> SendOptions {
+    SendOptions::default()
+}
+
+/// Sends an envoy FROM the foreign jurisdiction TO Rust with delivery options.
+///
+/// `options` sets the priority lane and an optional TTL or absolute deadline.
+/// An envoy that expires before Rust receives it is skipped, counted in the
+/// `expired` counter and reported to the expiry callback.
+///
+/// # Returns
+/// * `-3` - `payload` or `options` is NULL
+/// * `-12` - `options->priority` names no lane
+/// * Otherwise as `send_envoy`
+///
+/// # Safety
+/// * `options` must be NULL or point to a valid `praborrow_send_options`.
+/// * Otherwise as `send_envoy`
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(payload, options))]
+pub unsafe extern "C" fn send_envoy_with_options(
+    id: u32,
+    payload: *const c_char,
+    options: *const SendOptions,
+) -> c_int {
+    let Some(registry) = current_registry() else {
+        return not_established("send_envoy_with_options");
+    };
+    match unsafe { resolve_options("send_envoy_with_options", id, options) } {
+        Ok(routing) => unsafe {
+            send_envoy_in(&registry, "send_envoy_with_options", id, routing, payload)
+        },
+        Err(code) => code,
+    }
+}
+
+/// Sends an envoy with delivery options on a specific context.
+///
+/// # Returns
+/// * `-3` - Additionally, `ctx` is NULL
+/// * Otherwise as `send_envoy_with_options`
+///
+/// # Safety
+/// * `ctx` must be a live pointer returned by `praborrow_context_new`.
+/// * Otherwise as `send_envoy_with_options`
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(ctx, payload, options))]
+pub unsafe extern "C" fn send_envoy_with_options_ctx(
+    ctx: *const Context,
+    id: u32,
+    payload: *const c_char,
+    options: *const SendOptions,
+) -> c_int {
+    let Some(ctx) = (unsafe { Context::from_ptr(ctx) }) else {
+        return null_context("send_envoy_with_options_ctx");
+    };
+    match unsafe { resolve_options("send_envoy_with_options_ctx", id, options) } {
+        Ok(routing) => unsafe {
+            send_envoy_in(
+                ctx.registry(),
+                "send_envoy_with_options_ctx",
+                id,
+                routing,
+                payload,
+            )
+        },
+        Err(code) => code,
+    }
+}
+
+/// Sends a length-delimited binary envoy with delivery options.
+///
+/// # Returns
+/// * `-3` - `options` is NULL, or `data` is NULL with a non-zero `len`
+/// * `-12` - `options->priority` names no lane
+/// * Otherwise as `send_envoy_bytes`
+///
+/// # Safety
+/// * `options` must be NULL or point to a valid `praborrow_send_options`.
+/// * Otherwise as `send_envoy_bytes`
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(data, options))]
+pub unsafe extern "C" fn send_envoy_bytes_with_options(
+    id: u32,
+    data: *const u8,
+    len: usize,
+    options: *const SendOptions,
+) -> c_int {
+    let Some(registry) = current_registry() else {
+        return not_established("send_envoy_bytes_with_options");
+    };
+    match unsafe { resolve_options("send_envoy_bytes_with_options", id, options) } {
+        Ok(routing) => unsafe {
+            send_envoy_bytes_in(
+                &registry,
+                "send_envoy_bytes_with_options",
+                id,
+                routing,
+                data,
+                len,
+            )
+        },
+        Err(code) => code,
+    }
+}
+
+/// Sends a length-delimited binary envoy with delivery options on a specific context.
+///
+/// # Returns
+/// * `-3` - Additionally, `ctx` is NULL
+/// * Otherwise as `send_envoy_bytes_with_options`
+///
+/// # Safety
+/// * `ctx` must be a live pointer returned by `praborrow_context_new`.
+/// * Otherwise as `send_envoy_bytes_with_options`
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(ctx, data, options))]
+pub unsafe extern "C" fn send_envoy_bytes_with_options_ctx(
+    ctx: *const Context,
+    id: u32,
+    data: *const u8,
+    len: usize,
+    options: *const SendOptions,
+) -> c_int {
+    let Some(ctx) = (unsafe { Context::from_ptr(ctx) }) else {
+        return null_context("send_envoy_bytes_with_options_ctx");
+    };
+    match unsafe { resolve_options("send_envoy_bytes_with_options_ctx", id, options) } {
+        Ok(routing) => unsafe {
+            send_envoy_bytes_in(
+                ctx.registry(),
+                "send_envoy_bytes_with_options_ctx",
+                id,
+                routing,
+                data,
+                len,
+            )
+        },
+        Err(code) => code,
+    }
+}
+
+/// Validates foreign send options of envoy `id`, mapping failures to error codes.
+///
+/// # Safety
+/// * `options` must be NULL or point to a valid `SendOptions`.
+unsafe fn resolve_options(
+    function: &'static str,
+    id: u32,
+    options: *const SendOptions,
+) -> Result<Routing, c_int> {
+    let Some(options) = (unsafe { options.as_ref() }) else {
+        tracing::error!("Received NULL send options");
+        return Err(last_error::fail(
+            ERR_NULL_PTR,
+            function,
+            Some(id),
+            "options is NULL",
+        ));
+    };
+    options
+        .resolve()
+        .map_err(|_| invalid_priority(function, id, options.priority))
+}
+
+/// Reports a priority that names no lane.
+fn invalid_priority(function: &'static str, id: u32, priority: u8) -> c_int {
+    tracing::error!(envoy_id = id, priority, "Invalid priority");
+    last_error::fail(
+        ERR_INVALID_PRIORITY,
+        function,
+        Some(id),
+        format_args!("priority {priority} names no lane"),
+    )
+}
+
+/// Reports a call made before relations were established.
+fn not_established(function: &'static str) -> c_int {
+    last_error::fail(
+        ERR_NOT_INITIALIZED,
+        function,
+        None,
+        "relations are not established",
+    )
+}
+
+/// Reports a `_ctx` call without a context.
+fn null_context(function: &'static str) -> c_int {
+    last_error::fail(ERR_NULL_PTR, function, None, "ctx is NULL")
+}
+
+/// Queues a validated envoy from the foreign side, applying backpressure.
+fn deliver_envoy(
+    registry: &GlobalRegistry,
+    function: &'static str,
+    id: u32,
+    routing: Routing,
+    payload: Vec<u8>,
+) -> c_int {
+    if registry.is_closed() {
+        tracing::warn!(envoy_id = id, "Envoy rejected: relations severed");
+        return last_error::fail(ERR_CLOSED, function, Some(id), "relations are severed");
+    }
+
+    // OOM Prevention: Check Limits
+    let len = payload.len();
+    let admission = match registry.admit(Direction::Incoming, len, routing.priority) {
+        Ok(admission) => admission,
+        Err(safe::DiplomacyError::Closed) => {
+            return last_error::fail(ERR_CLOSED, function, Some(id), "relations are severed");
         }
-    } else {
-        // Double free or invalid pointer!
-        // We log error and DO NOT attempt to free, preventing segfault/heap corruption.
-        tracing::error!(
-            event = "ffi_violation",
-            ptr = ?envoy,
-            "Attempted to free invalid or already freed envoy pointer"
-        );
+        Err(e) => {
+            return last_error::fail(
+                ERR_QUEUE_FULL,
+                function,
+                Some(id),
+                format_args!("{e} ({len} bytes, {:?} priority)", routing.priority),
+            );
+        }
+    };
+
+    let envelope = registry.seal(id, routing, payload);
+    if admission == Admission::Spill {
+        // Spilled envoys never reach Rust, so there is nothing to reply to yet.
+        registry.spill(Direction::Incoming, envelope);
+        return SUCCESS;
+    }
+    let reply = registry.reply_to(&envelope);
+    registry.incoming.push(envelope);
+
+    // The envoy itself was delivered; a reply that does not fit is dropped
+    // rather than failing the foreign call.
+    if let Some(reply) = reply
+        && let Err(e) = registry.dispatch(id, Routing::with_priority(routing.priority), &reply)
+    {
+        tracing::warn!(envoy_id = id, error = %e, "Reply dropped");
     }
+
+    SUCCESS
 }
 
-/// Returns the version of the PraBorrow diplomacy crate.
+/// Receives an envoy (message) FROM Rust TO the foreign jurisdiction.
+///
+/// Pops a message from the outbox of the default context. A message that
+/// contains a NUL byte cannot be represented as a C string; it stays at the
+/// head of the outbox and `praborrow_last_error_code()` reports `-16`.
+/// Receive it with `receive_envoy_bytes`, `receive_envoy_into` or
+/// `receive_envoy_handle`, which also suit binary traffic in general.
+///
+/// # Returns
+/// * `char*` - Pointer to null-terminated string. Ownership transferred to caller.
+/// * `NULL` - No messages available, the next one contains a NUL byte, or error.
 #[unsafe(no_mangle)]
-pub extern "C" fn praborrow_version() -> *const c_char {
-    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr() as *const c_char
+#[tracing::instrument]
+pub extern "C" fn receive_envoy() -> *mut c_char {
+    match current_registry() {
+        Some(registry) => receive_envoy_in(&registry, "receive_envoy"),
+        None => std::ptr::null_mut(),
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Receives an envoy FROM Rust TO the foreign jurisdiction on a specific context.
+///
+/// The returned pointer must be released with `free_envoy_ctx` on the same context.
+///
+/// # Returns
+/// * `char*` - Pointer to null-terminated string. Ownership transferred to caller.
+/// * `NULL` - No messages available, the next one contains a NUL byte,
+///   `ctx` is NULL, or error.
+///
+/// # Safety
+/// * `ctx` must be a live pointer returned by `praborrow_context_new`.
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(ctx))]
+pub unsafe extern "C" fn receive_envoy_ctx(ctx: *const Context) -> *mut c_char {
+    match unsafe { Context::from_ptr(ctx) } {
+        Some(ctx) => receive_envoy_in(ctx.registry(), "receive_envoy_ctx"),
+        None => std::ptr::null_mut(),
+    }
+}
 
-    #[test]
-    fn test_diplomacy_flow() {
-        // 1. Establish relations
-        // Note: tests run in parallel, so REGISTRY might already be set.
-        // We handle returns gracefully.
-        let status = establish_relations();
-        assert!(status == 0 || status == -1);
+/// Shared implementation of `receive_envoy` and `receive_envoy_ctx`.
+fn receive_envoy_in(registry: &GlobalRegistry, function: &'static str) -> *mut c_char {
+    lend_c_string(registry, function, registry.outbox.pop())
+}
 
-        // 2. Send envoy (C -> Rust)
-        let msg = CString::new("Hello from C").unwrap();
-        let send_status = unsafe { send_envoy(101, msg.as_ptr()) };
-        assert_eq!(send_status, 0);
+/// Hands a payload to the foreign side as a C string, registering it as a `LoanKind::CString`.
+///
+/// A payload with a NUL byte is put back at the head of the outbox instead.
+fn lend_c_string(
+    registry: &GlobalRegistry,
+    function: &'static str,
+    msg: Option<Envelope>,
+) -> *mut c_char {
+    match msg {
+        Some(envelope) => {
+            let len = envelope.payload.len();
+            match CString::new(envelope.payload) {
+                Ok(c_str) => {
+                    log_lent(registry, envelope.id, c_str.as_bytes());
+                    let ptr = c_str.into_raw();
+                    // Register the pointer as active
+                    let loan = Loan::new(LoanKind::CString, envelope.id, len);
+                    registry.loans.lend_pointer(ptr as usize, loan);
+                    ptr
+                }
+                Err(e) => {
+                    let nul_position = e.nul_position();
+                    tracing::error!(
+                        event = "envoy_contains_nul",
+                        envoy_id = envelope.id,
+                        sequence = envelope.sequence,
+                        nul_position,
+                        len,
+                        "Envoy contains a NUL byte and cannot be received as a C string"
+                    );
+                    let id = envelope.id;
+                    registry.outbox.restore(Envelope {
+                        payload: e.into_vec(),
+                        ..envelope
+                    });
+                    last_error::fail(
+                        ERR_CONTAINS_NUL,
+                        function,
+                        Some(id),
+                        format_args!(
+                            "NUL byte at {nul_position}; receive it with \
+                             receive_envoy_bytes, receive_envoy_into or receive_envoy_handle"
+                        ),
+                    );
+                    std::ptr::null_mut()
+                }
+            }
+        }
+        None => std::ptr::null_mut(),
+    }
+}
 
-        // 3. Receive envoy (Rust -> C) - should contain the Ack
-        let received_ptr = receive_envoy();
-        assert!(!received_ptr.is_null());
+/// Receives an envoy FROM Rust TO the foreign jurisdiction, waiting up to
+/// `timeout_ms` for one to arrive.
+///
+/// A `timeout_ms` of `UINT32_MAX` waits indefinitely. The call is woken by
+/// `Diplomat::send` and returns NULL early if relations are severed.
+///
+/// # Returns
+/// * `char*` - Pointer to null-terminated string. Ownership transferred to caller.
+/// * `NULL` - Timed out, relations severed, or error.
+#[unsafe(no_mangle)]
+#[tracing::instrument]
+pub extern "C" fn receive_envoy_timeout(timeout_ms: u32) -> *mut c_char {
+    match current_registry() {
+        Some(registry) => lend_c_string(
+            &registry,
+            "receive_envoy_timeout",
+            registry.outbox.pop_wait(deadline_after(timeout_ms)),
+        ),
+        None => std::ptr::null_mut(),
+    }
+}
 
-        let received_str = unsafe { CStr::from_ptr(received_ptr).to_str().unwrap() };
-        assert_eq!(received_str, "Ack: Hello from C");
+/// Receives an envoy on a specific context, waiting up to `timeout_ms` for one to arrive.
+///
+/// The returned pointer must be released with `free_envoy_ctx` on the same context.
+///
+/// # Safety
+/// * `ctx` must be a live pointer returned by `praborrow_context_new`.
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(ctx))]
+pub unsafe extern "C" fn receive_envoy_timeout_ctx(
+    ctx: *const Context,
+    timeout_ms: u32,
+) -> *mut c_char {
+    match unsafe { Context::from_ptr(ctx) } {
+        Some(ctx) => {
+            let registry = ctx.registry();
+            lend_c_string(
+                registry,
+                "receive_envoy_timeout_ctx",
+                registry.outbox.pop_wait(deadline_after(timeout_ms)),
+            )
+        }
+        None => std::ptr::null_mut(),
+    }
+}
 
-        // 4. Free envoy
-        unsafe { free_envoy(received_ptr) };
+/// Converts a foreign timeout into a deadline; `u32::MAX` means no deadline.
+fn deadline_after(timeout_ms: u32) -> Option<Instant> {
+    (timeout_ms != u32::MAX).then(|| Instant::now() + Duration::from_millis(u64::from(timeout_ms)))
+}
 
-        // 5. Receive empty
-        let empty = receive_envoy();
-        assert!(empty.is_null());
+/// Receives a length-delimited binary envoy FROM Rust TO the foreign jurisdiction.
+///
+/// # Arguments
+/// * `len` - Out-parameter receiving the payload length
+///
+/// # Returns
+/// * `uint8_t*` - Pointer to `*len` bytes. Ownership transferred to caller;
+///   release with `free_envoy_bytes`.
+/// * `NULL` - No messages available, `len` is NULL, or error. `*len` is set to 0.
+///
+/// # Safety
+/// * `len` must be NULL or valid for writes.
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(len))]
+pub unsafe extern "C" fn receive_envoy_bytes(len: *mut usize) -> *mut u8 {
+    match current_registry() {
+        Some(registry) => unsafe { receive_envoy_bytes_in(&registry, len) },
+        None => unsafe { empty_bytes(len) },
+    }
+}
+
+/// Receives a length-delimited binary envoy on a specific context.
+///
+/// The returned pointer must be released with `free_envoy_bytes_ctx` on the same context.
+///
+/// # Safety
+/// * `ctx` must be a live pointer returned by `praborrow_context_new`.
+/// * `len` must be NULL or valid for writes.
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(ctx, len))]
+pub unsafe extern "C" fn receive_envoy_bytes_ctx(ctx: *const Context, len: *mut usize) -> *mut u8 {
+    match unsafe { Context::from_ptr(ctx) } {
+        Some(ctx) => unsafe { receive_envoy_bytes_in(ctx.registry(), len) },
+        None => unsafe { empty_bytes(len) },
+    }
+}
+
+/// Reports "no envoy" to a `receive_envoy_bytes` caller.
+///
+/// # Safety
+/// `len` must be NULL or valid for writes.
+unsafe fn empty_bytes(len: *mut usize) -> *mut u8 {
+    if !len.is_null() {
+        unsafe { *len = 0 };
+    }
+    std::ptr::null_mut()
+}
+
+/// Shared implementation of `receive_envoy_bytes` and `receive_envoy_bytes_ctx`.
+///
+/// # Safety
+/// `len` must be NULL or valid for writes.
+unsafe fn receive_envoy_bytes_in(registry: &GlobalRegistry, len: *mut usize) -> *mut u8 {
+    if len.is_null() {
+        tracing::error!("Received NULL length pointer");
+        return std::ptr::null_mut();
+    }
+
+    let Some(envelope) = registry.outbox.pop() else {
+        return unsafe { empty_bytes(len) };
+    };
+
+    let payload_len = envelope.payload.len();
+    let ptr = lend_bytes(registry, envelope.id, envelope.payload);
+    unsafe { *len = payload_len };
+    ptr
+}
+
+/// Hands the payload of envoy `id` to the foreign side, registering it as a
+/// `LoanKind::Bytes`.
+fn lend_bytes(registry: &GlobalRegistry, id: u32, bytes: Vec<u8>) -> *mut u8 {
+    // Never hand out a zero-sized allocation: its dangling address would be
+    // shared by every empty envoy and collide in the loan ledger.
+    let len = bytes.len();
+    let boxed = if bytes.is_empty() {
+        vec![0u8].into_boxed_slice()
+    } else {
+        bytes.into_boxed_slice()
+    };
+    log_lent(registry, id, &boxed[..len]);
+    let alloc_len = boxed.len();
+    let ptr = Box::into_raw(boxed) as *mut u8;
+
+    let loan = Loan::new(LoanKind::Bytes { alloc_len }, id, len);
+    registry.loans.lend_pointer(ptr as usize, loan);
+    ptr
+}
+
+/// Logs that the foreign side took the payload of envoy `id`.
+fn log_lent(registry: &GlobalRegistry, id: u32, payload: &[u8]) {
+    tracing::debug!(
+        event = "envoy_lent",
+        envoy_id = id,
+        len = payload.len(),
+        payload = logging::payload(registry.payload_log, payload),
+        "Envoy received by foreign jurisdiction"
+    );
+}
+
+/// Receives an envoy FROM Rust TO the foreign jurisdiction into a buffer
+/// owned by the caller.
+///
+/// Copies the payload followed by a terminating NUL into `buf`; nothing is
+/// allocated and nothing needs to be freed. `*needed` is set to the size the
+/// envoy requires, payload length plus one. If that exceeds `cap` the envoy
+/// stays at the head of the outbox, so the caller can grow the buffer and try
+/// again; `buf` may be NULL with `cap` 0 to query the size. The payload may
+/// contain NUL bytes; its length is `*needed - 1`.
+///
+/// # Returns
+/// * `0` - Success, `*needed` bytes written to `buf`
+/// * `1` - No messages available, `*needed` is 0
+/// * `-3` - `needed` is NULL, or `buf` is NULL with a non-zero `cap`
+/// * `-13` - `buf` is too small; the envoy was left queued
+/// * `-15` - Registry not initialized
+///
+/// # Safety
+/// * `buf` must be NULL or valid for writes of `cap` bytes.
+/// * `needed` must be NULL or valid for writes.
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(buf, needed))]
+pub unsafe extern "C" fn receive_envoy_into(
+    buf: *mut c_char,
+    cap: usize,
+    needed: *mut usize,
+) -> c_int {
+    match current_registry() {
+        Some(registry) => unsafe {
+            receive_envoy_into_in(&registry, "receive_envoy_into", buf, cap, needed)
+        },
+        None => not_established("receive_envoy_into"),
+    }
+}
+
+/// Receives an envoy on a specific context into a buffer owned by the caller.
+///
+/// # Returns
+/// As `receive_envoy_into`, or `-3` if `ctx` is NULL.
+///
+/// # Safety
+/// * `ctx` must be a live pointer returned by `praborrow_context_new`.
+/// * `buf` must be NULL or valid for writes of `cap` bytes.
+/// * `needed` must be NULL or valid for writes.
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(ctx, buf, needed))]
+pub unsafe extern "C" fn receive_envoy_into_ctx(
+    ctx: *const Context,
+    buf: *mut c_char,
+    cap: usize,
+    needed: *mut usize,
+) -> c_int {
+    match unsafe { Context::from_ptr(ctx) } {
+        Some(ctx) => unsafe {
+            receive_envoy_into_in(ctx.registry(), "receive_envoy_into_ctx", buf, cap, needed)
+        },
+        None => null_context("receive_envoy_into_ctx"),
+    }
+}
+
+/// Shared implementation of `receive_envoy_into` and `receive_envoy_into_ctx`.
+///
+/// # Safety
+/// `buf` must be NULL or valid for writes of `cap` bytes; `needed` must be
+/// NULL or valid for writes.
+unsafe fn receive_envoy_into_in(
+    registry: &GlobalRegistry,
+    function: &'static str,
+    buf: *mut c_char,
+    cap: usize,
+    needed: *mut usize,
+) -> c_int {
+    if needed.is_null() || (buf.is_null() && cap > 0) {
+        tracing::error!("Received NULL buffer or size pointer");
+        let reason = if needed.is_null() {
+            "needed is NULL"
+        } else {
+            "buf is NULL with a non-zero cap"
+        };
+        return last_error::fail(ERR_NULL_PTR, function, None, reason);
+    }
+
+    // Sizing is answered by a peek, so querying never disturbs the outbox.
+    let Some((id, len)) = registry.outbox.peek() else {
+        unsafe { *needed = 0 };
+        return EMPTY;
+    };
+    unsafe { *needed = len + 1 };
+    if len >= cap {
+        return buffer_too_small(function, id, cap, len);
+    }
+
+    let Some(envelope) = registry.outbox.pop() else {
+        unsafe { *needed = 0 };
+        return EMPTY;
+    };
+    // A concurrent receiver may have taken the peeked envoy first.
+    let len = envelope.payload.len();
+    unsafe { *needed = len + 1 };
+    if len >= cap {
+        let id = envelope.id;
+        registry.outbox.restore(envelope);
+        return buffer_too_small(function, id, cap, len);
+    }
+
+    // SAFETY: `len + 1 <= cap` bytes of `buf` are writable and cannot overlap
+    // the payload, which Rust owns.
+    unsafe {
+        std::ptr::copy_nonoverlapping(envelope.payload.as_ptr(), buf.cast::<u8>(), len);
+        *buf.add(len) = 0;
+    }
+    log_lent(registry, envelope.id, &envelope.payload);
+    SUCCESS
+}
+
+/// Records that envoy `id` of `len` payload bytes does not fit in `cap` bytes.
+fn buffer_too_small(function: &'static str, id: u32, cap: usize, len: usize) -> c_int {
+    last_error::fail(
+        ERR_BUFFER_TOO_SMALL,
+        function,
+        Some(id),
+        format_args!("buffer holds {cap} bytes, envoy needs {}", len + 1),
+    )
+}
+
+/// Receives an envoy FROM Rust TO the foreign jurisdiction as a handle.
+///
+/// The envoy stays owned by Rust; read it with `envoy_data`, `envoy_len` and
+/// `envoy_id`, and return it with `envoy_release`. Handles carry a generation,
+/// so using one after its release is detected even if the slot was reused.
+///
+/// # Returns
+/// * Non-zero handle - Ownership of the loan transferred to caller.
+/// * `0` - No messages available or registry not initialized.
+#[unsafe(no_mangle)]
+#[tracing::instrument]
+pub extern "C" fn receive_envoy_handle() -> EnvoyHandle {
+    match current_registry() {
+        Some(registry) => receive_envoy_handle_in(&registry),
+        None => EnvoyHandle::NONE,
+    }
+}
+
+/// Receives an envoy on a specific context as a handle.
+///
+/// The handle must be used with the `_ctx` accessors on the same context.
+///
+/// # Safety
+/// * `ctx` must be a live pointer returned by `praborrow_context_new`.
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(ctx))]
+pub unsafe extern "C" fn receive_envoy_handle_ctx(ctx: *const Context) -> EnvoyHandle {
+    match unsafe { Context::from_ptr(ctx) } {
+        Some(ctx) => receive_envoy_handle_in(ctx.registry()),
+        None => EnvoyHandle::NONE,
+    }
+}
+
+/// Shared implementation of `receive_envoy_handle` and `receive_envoy_handle_ctx`.
+fn receive_envoy_handle_in(registry: &GlobalRegistry) -> EnvoyHandle {
+    match registry.outbox.pop() {
+        Some(envelope) => {
+            log_lent(registry, envelope.id, &envelope.payload);
+            registry.loans.lend_handle(envelope)
+        }
+        None => EnvoyHandle::NONE,
+    }
+}
+
+/// Returns the payload of a loaned envoy.
+///
+/// # Returns
+/// * `const uint8_t*` - `envoy_len(handle)` bytes, valid until `envoy_release`;
+///   a payload never released stays valid after relations are severed.
+/// * `NULL` - `handle` is not on loan (released, stale or never issued).
+#[unsafe(no_mangle)]
+pub extern "C" fn envoy_data(handle: EnvoyHandle) -> *const u8 {
+    current_registry().map_or(std::ptr::null(), |registry| {
+        envoy_data_in(&registry, handle)
+    })
+}
+
+/// Returns the payload of an envoy loaned by `receive_envoy_handle_ctx`.
+///
+/// # Safety
+/// * `ctx` must be a live pointer returned by `praborrow_context_new`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn envoy_data_ctx(ctx: *const Context, handle: EnvoyHandle) -> *const u8 {
+    unsafe { Context::from_ptr(ctx) }.map_or(std::ptr::null(), |ctx| {
+        envoy_data_in(ctx.registry(), handle)
+    })
+}
+
+/// Shared implementation of `envoy_data` and `envoy_data_ctx`.
+fn envoy_data_in(registry: &GlobalRegistry, handle: EnvoyHandle) -> *const u8 {
+    // The payload buffer does not move while the envelope sits in its slot.
+    registry
+        .loans
+        .with_handle(handle, |envelope| envelope.payload.as_ptr())
+        .unwrap_or_else(|| stale_handle(handle, std::ptr::null()))
+}
+
+/// Returns the payload length of a loaned envoy, or 0 if `handle` is not on loan.
+#[unsafe(no_mangle)]
+pub extern "C" fn envoy_len(handle: EnvoyHandle) -> usize {
+    current_registry().map_or(0, |registry| envoy_len_in(&registry, handle))
+}
+
+/// Returns the payload length of an envoy loaned by `receive_envoy_handle_ctx`.
+///
+/// # Safety
+/// * `ctx` must be a live pointer returned by `praborrow_context_new`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn envoy_len_ctx(ctx: *const Context, handle: EnvoyHandle) -> usize {
+    unsafe { Context::from_ptr(ctx) }.map_or(0, |ctx| envoy_len_in(ctx.registry(), handle))
+}
+
+/// Shared implementation of `envoy_len` and `envoy_len_ctx`.
+fn envoy_len_in(registry: &GlobalRegistry, handle: EnvoyHandle) -> usize {
+    registry
+        .loans
+        .with_handle(handle, |envelope| envelope.payload.len())
+        .unwrap_or_else(|| stale_handle(handle, 0))
+}
+
+/// Returns the id of a loaned envoy, or 0 if `handle` is not on loan.
+#[unsafe(no_mangle)]
+pub extern "C" fn envoy_id(handle: EnvoyHandle) -> u32 {
+    current_registry().map_or(0, |registry| envoy_id_in(&registry, handle))
+}
+
+/// Returns the id of an envoy loaned by `receive_envoy_handle_ctx`.
+///
+/// # Safety
+/// * `ctx` must be a live pointer returned by `praborrow_context_new`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn envoy_id_ctx(ctx: *const Context, handle: EnvoyHandle) -> u32 {
+    unsafe { Context::from_ptr(ctx) }.map_or(0, |ctx| envoy_id_in(ctx.registry(), handle))
+}
+
+/// Shared implementation of `envoy_id` and `envoy_id_ctx`.
+fn envoy_id_in(registry: &GlobalRegistry, handle: EnvoyHandle) -> u32 {
+    registry
+        .loans
+        .with_handle(handle, |envelope| envelope.id)
+        .unwrap_or_else(|| stale_handle(handle, 0))
+}
+
+/// Ends the loan of an envoy received with `receive_envoy_handle`.
+///
+/// Afterwards `handle` is invalid: every accessor rejects it, even once its
+/// slot holds another envoy.
+///
+/// # Returns
+/// * `0` - Success
+/// * `-14` - `handle` is not on loan (already released, stale or never issued)
+/// * `-15` - Registry not initialized
+#[unsafe(no_mangle)]
+pub extern "C" fn envoy_release(handle: EnvoyHandle) -> c_int {
+    match current_registry() {
+        Some(registry) => envoy_release_in(&registry, "envoy_release", handle),
+        None => not_established("envoy_release"),
+    }
+}
+
+/// Ends the loan of an envoy received with `receive_envoy_handle_ctx`.
+///
+/// # Returns
+/// As `envoy_release`, or `-3` if `ctx` is NULL.
+///
+/// # Safety
+/// * `ctx` must be a live pointer returned by `praborrow_context_new`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn envoy_release_ctx(ctx: *const Context, handle: EnvoyHandle) -> c_int {
+    match unsafe { Context::from_ptr(ctx) } {
+        Some(ctx) => envoy_release_in(ctx.registry(), "envoy_release_ctx", handle),
+        None => null_context("envoy_release_ctx"),
+    }
+}
+
+/// Shared implementation of `envoy_release` and `envoy_release_ctx`.
+fn envoy_release_in(
+    registry: &GlobalRegistry,
+    function: &'static str,
+    handle: EnvoyHandle,
+) -> c_int {
+    match registry.loans.return_handle(handle) {
+        Some(_) => SUCCESS,
+        None => stale_handle(
+            handle,
+            last_error::fail(
+                ERR_INVALID_HANDLE,
+                function,
+                None,
+                format_args!("handle {:#x} is not on loan", handle.0),
+            ),
+        ),
+    }
+}
+
+/// Logs use of a handle that is not on loan and returns `fallback`.
+fn stale_handle<T>(handle: EnvoyHandle, fallback: T) -> T {
+    tracing::error!(
+        event = "ffi_violation",
+        handle = handle.0,
+        "Used an envoy handle that is not on loan"
+    );
+    fallback
+}
+
+/// Receives an envoy with its metadata FROM Rust TO the foreign jurisdiction.
+///
+/// Fills `out` with the id, sequence number, timestamp and payload of the next
+/// envoy in the outbox of the default context. `out->data` is on loan to the
+/// caller and must be released with `free_envoy_bytes`.
+///
+/// # Returns
+/// * `0` - Success, `out` filled
+/// * `1` - No messages available, `out` zeroed
+/// * `-3` - `out` is NULL
+/// * `-15` - Registry not initialized
+///
+/// # Safety
+/// * `out` must be NULL or valid for writes.
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(out))]
+pub unsafe extern "C" fn receive_envoy_envelope(out: *mut Envoy) -> c_int {
+    match current_registry() {
+        Some(registry) => unsafe {
+            receive_envoy_envelope_in(
+                &registry,
+                "receive_envoy_envelope",
+                out,
+                Some(Instant::now()),
+            )
+        },
+        None => not_established("receive_envoy_envelope"),
+    }
+}
+
+/// Receives an envoy with its metadata on a specific context.
+///
+/// `out->data` must be released with `free_envoy_bytes_ctx` on the same context.
+///
+/// # Returns
+/// * `-3` - `ctx` or `out` is NULL
+/// * Otherwise as `receive_envoy_envelope`
+///
+/// # Safety
+/// * `ctx` must be a live pointer returned by `praborrow_context_new`.
+/// * `out` must be NULL or valid for writes.
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(ctx, out))]
+pub unsafe extern "C" fn receive_envoy_envelope_ctx(ctx: *const Context, out: *mut Envoy) -> c_int {
+    match unsafe { Context::from_ptr(ctx) } {
+        Some(ctx) => unsafe {
+            receive_envoy_envelope_in(
+                ctx.registry(),
+                "receive_envoy_envelope_ctx",
+                out,
+                Some(Instant::now()),
+            )
+        },
+        None => null_context("receive_envoy_envelope_ctx"),
+    }
+}
+
+/// Receives an envoy with its metadata, waiting up to `timeout_ms` for one to arrive.
+///
+/// A `timeout_ms` of `UINT32_MAX` waits indefinitely. Returns early with `-8`
+/// if relations are severed while waiting.
+///
+/// # Returns
+/// * `0` - Success, `out` filled
+/// * `1` - Timed out, `out` zeroed
+/// * `-3` - `out` is NULL
+/// * `-8` - Relations severed
+/// * `-15` - Registry not initialized
+///
+/// # Safety
+/// * `out` must be NULL or valid for writes.
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(out))]
+pub unsafe extern "C" fn receive_envoy_envelope_timeout(out: *mut Envoy, timeout_ms: u32) -> c_int {
+    match current_registry() {
+        Some(registry) => unsafe {
+            receive_envoy_envelope_in(
+                &registry,
+                "receive_envoy_envelope_timeout",
+                out,
+                deadline_after(timeout_ms),
+            )
+        },
+        None => not_established("receive_envoy_envelope_timeout"),
+    }
+}
+
+/// Receives an envoy with its metadata on a specific context, waiting up to `timeout_ms`.
+///
+/// # Returns
+/// * `-3` - `ctx` or `out` is NULL
+/// * Otherwise as `receive_envoy_envelope_timeout`
+///
+/// # Safety
+/// * `ctx` must be a live pointer returned by `praborrow_context_new`.
+/// * `out` must be NULL or valid for writes.
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(ctx, out))]
+pub unsafe extern "C" fn receive_envoy_envelope_timeout_ctx(
+    ctx: *const Context,
+    out: *mut Envoy,
+    timeout_ms: u32,
+) -> c_int {
+    match unsafe { Context::from_ptr(ctx) } {
+        Some(ctx) => unsafe {
+            receive_envoy_envelope_in(
+                ctx.registry(),
+                "receive_envoy_envelope_timeout_ctx",
+                out,
+                deadline_after(timeout_ms),
+            )
+        },
+        None => null_context("receive_envoy_envelope_timeout_ctx"),
+    }
+}
+
+/// Shared implementation of the `receive_envoy_envelope*` functions.
+///
+/// A `deadline` in the past makes the call non-blocking; `None` waits indefinitely.
+///
+/// # Safety
+/// `out` must be NULL or valid for writes.
+unsafe fn receive_envoy_envelope_in(
+    registry: &GlobalRegistry,
+    function: &'static str,
+    out: *mut Envoy,
+    deadline: Option<Instant>,
+) -> c_int {
+    if out.is_null() {
+        tracing::error!("Received NULL envoy pointer");
+        return last_error::fail(ERR_NULL_PTR, function, None, "out is NULL");
+    }
+
+    let Some(envelope) = registry.outbox.pop_wait(deadline) else {
+        unsafe { out.write(Envoy::empty()) };
+        return if registry.outbox.is_closed() {
+            last_error::fail(ERR_CLOSED, function, None, "relations are severed")
+        } else {
+            EMPTY
+        };
+    };
+
+    unsafe { out.write(lend_envelope(registry, envelope)) };
+    SUCCESS
+}
+
+/// Describes `envelope` to the foreign side, lending it the payload.
+fn lend_envelope(registry: &GlobalRegistry, envelope: Envelope) -> Envoy {
+    Envoy {
+        id: envelope.id,
+        sequence: envelope.sequence,
+        timestamp_us: envelope.timestamp_micros(),
+        len: envelope.payload.len(),
+        data: lend_bytes(registry, envelope.id, envelope.payload),
+    }
+}
+
+/// Sends many envoys FROM the foreign jurisdiction TO Rust in one call.
+///
+/// Each element supplies `id`, `data` and `len`; `sequence` and
+/// `timestamp_us` are ignored. Envoys are queued in order, stopping at the
+/// first one that fails, so `*sent` envoys are queued and `envoys[*sent]` is
+/// the one that failed.
+///
+/// # Returns
+/// * `0` - Every envoy was queued
+/// * `-3` - `envoys` is NULL with a non-zero `n`
+/// * `-15` - Registry not initialized
+/// * Otherwise the `send_envoy_bytes` error of `envoys[*sent]`
+///
+/// # Safety
+/// * `envoys` must be valid for reads of `n` elements, each of whose `data`
+///   is valid for reads of `len` bytes.
+/// * `sent` must be NULL or valid for writes.
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(envoys, sent))]
+pub unsafe extern "C" fn send_envoys(envoys: *const Envoy, n: usize, sent: *mut usize) -> c_int {
+    match current_registry() {
+        Some(registry) => unsafe { send_envoys_in(&registry, "send_envoys", envoys, n, sent) },
+        None => unsafe { report_count(sent, 0, not_established("send_envoys")) },
+    }
+}
+
+/// Sends many envoys on a specific context in one call.
+///
+/// # Returns
+/// * `-3` - Additionally, `ctx` is NULL
+/// * Otherwise as `send_envoys`
+///
+/// # Safety
+/// * `ctx` must be a live pointer returned by `praborrow_context_new`.
+/// * Otherwise as `send_envoys`
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(ctx, envoys, sent))]
+pub unsafe extern "C" fn send_envoys_ctx(
+    ctx: *const Context,
+    envoys: *const Envoy,
+    n: usize,
+    sent: *mut usize,
+) -> c_int {
+    match unsafe { Context::from_ptr(ctx) } {
+        Some(ctx) => unsafe { send_envoys_in(ctx.registry(), "send_envoys_ctx", envoys, n, sent) },
+        None => unsafe { report_count(sent, 0, null_context("send_envoys_ctx")) },
+    }
+}
+
+/// Shared implementation of `send_envoys` and `send_envoys_ctx`.
+///
+/// # Safety
+/// See `send_envoys`.
+unsafe fn send_envoys_in(
+    registry: &GlobalRegistry,
+    function: &'static str,
+    envoys: *const Envoy,
+    n: usize,
+    sent: *mut usize,
+) -> c_int {
+    if envoys.is_null() && n != 0 {
+        tracing::error!(n, "Received NULL envoy array");
+        let status = last_error::fail(
+            ERR_NULL_PTR,
+            function,
+            None,
+            format_args!("envoys is NULL with n {n}"),
+        );
+        return unsafe { report_count(sent, 0, status) };
+    }
+    let envoys = if n == 0 {
+        &[]
+    } else {
+        unsafe { std::slice::from_raw_parts(envoys, n) }
+    };
+
+    for (index, envoy) in envoys.iter().enumerate() {
+        let status = if envoy.data.is_null() && envoy.len != 0 {
+            last_error::fail(
+                ERR_NULL_PTR,
+                function,
+                Some(envoy.id),
+                format_args!("envoys[{index}].data is NULL with len {}", envoy.len),
+            )
+        } else if envoy.id == 0 {
+            last_error::fail(
+                ERR_INVALID_ID,
+                function,
+                Some(envoy.id),
+                format_args!("envoys[{index}].id 0 is reserved"),
+            )
+        } else {
+            let payload = if envoy.len == 0 {
+                Vec::new()
+            } else {
+                unsafe { std::slice::from_raw_parts(envoy.data, envoy.len) }.to_vec()
+            };
+            deliver_envoy(registry, function, envoy.id, Routing::default(), payload)
+        };
+
+        if status != SUCCESS {
+            tracing::warn!(
+                envoy_id = envoy.id,
+                index,
+                status,
+                "Batch send stopped at failing envoy"
+            );
+            return unsafe { report_count(sent, index, status) };
+        }
+    }
+
+    tracing::debug!(
+        event = "envoys_received",
+        count = envoys.len(),
+        "Envoy batch received from foreign jurisdiction"
+    );
+    unsafe { report_count(sent, envoys.len(), SUCCESS) }
+}
+
+/// Writes `count` through an optional out-parameter and passes `status` through.
+///
+/// # Safety
+/// * `out` must be NULL or valid for writes.
+unsafe fn report_count(out: *mut usize, count: usize, status: c_int) -> c_int {
+    if let Some(out) = unsafe { out.as_mut() } {
+        *out = count;
+    }
+    status
+}
+
+/// Receives up to `max` envoys FROM Rust TO the foreign jurisdiction in one call.
+///
+/// Fills `out[0..n]` like `receive_envoy_envelope`, without blocking. Each
+/// `out[i].data` is on loan to the caller; release them one by one with
+/// `free_envoy_bytes`, or all at once with `free_envoys`.
+///
+/// # Returns
+/// * `>= 0` - Number of envoys written to `out`
+/// * `-3` - `out` is NULL with a non-zero `max`
+/// * `-15` - Registry not initialized
+///
+/// # Safety
+/// * `out` must be valid for writes of `max` elements.
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(out))]
+pub unsafe extern "C" fn receive_envoys(out: *mut Envoy, max: usize) -> c_int {
+    match current_registry() {
+        Some(registry) => unsafe { receive_envoys_in(&registry, "receive_envoys", out, max) },
+        None => not_established("receive_envoys"),
+    }
+}
+
+/// Receives up to `max` envoys from a specific context in one call.
+///
+/// # Returns
+/// * `-3` - Additionally, `ctx` is NULL
+/// * Otherwise as `receive_envoys`
+///
+/// # Safety
+/// * `ctx` must be a live pointer returned by `praborrow_context_new`.
+/// * `out` must be valid for writes of `max` elements.
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(ctx, out))]
+pub unsafe extern "C" fn receive_envoys_ctx(
+    ctx: *const Context,
+    out: *mut Envoy,
+    max: usize,
+) -> c_int {
+    match unsafe { Context::from_ptr(ctx) } {
+        Some(ctx) => unsafe { receive_envoys_in(ctx.registry(), "receive_envoys_ctx", out, max) },
+        None => null_context("receive_envoys_ctx"),
+    }
+}
+
+/// Shared implementation of `receive_envoys` and `receive_envoys_ctx`.
+///
+/// # Safety
+/// * `out` must be valid for writes of `max` elements.
+unsafe fn receive_envoys_in(
+    registry: &GlobalRegistry,
+    function: &'static str,
+    out: *mut Envoy,
+    max: usize,
+) -> c_int {
+    if out.is_null() && max != 0 {
+        tracing::error!("Received NULL envoy array");
+        return last_error::fail(
+            ERR_NULL_PTR,
+            function,
+            None,
+            format_args!("out is NULL with max {max}"),
+        );
+    }
+
+    // Never receive more than the return type can report.
+    let max = max.min(c_int::MAX as usize);
+    let mut received = 0;
+    while received < max {
+        let Some(envelope) = registry.outbox.pop() else {
+            break;
+        };
+        unsafe { out.add(received).write(lend_envelope(registry, envelope)) };
+        received += 1;
+    }
+    received as c_int
+}
+
+/// Returns every payload of an envoy array filled by `receive_envoys`.
+///
+/// Each `data` pointer is released as by `free_envoy_bytes` and set to NULL,
+/// so freeing the same array twice is harmless.
+///
+/// # Safety
+/// * `envoys` must be NULL or valid for reads and writes of `n` elements.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn free_envoys(envoys: *mut Envoy, n: usize) {
+    if let Some(registry) = current_registry() {
+        unsafe { free_envoys_in(&registry, envoys, n) };
+    }
+}
+
+/// Returns every payload of an envoy array filled by `receive_envoys_ctx`.
+///
+/// # Safety
+/// * `ctx` must be a live pointer returned by `praborrow_context_new`.
+/// * `envoys` must be NULL or valid for reads and writes of `n` elements.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn free_envoys_ctx(ctx: *const Context, envoys: *mut Envoy, n: usize) {
+    if let Some(ctx) = unsafe { Context::from_ptr(ctx) } {
+        unsafe { free_envoys_in(ctx.registry(), envoys, n) };
+    }
+}
+
+/// Shared implementation of `free_envoys` and `free_envoys_ctx`.
+///
+/// # Safety
+/// * `envoys` must be NULL or valid for reads and writes of `n` elements.
+unsafe fn free_envoys_in(registry: &GlobalRegistry, envoys: *mut Envoy, n: usize) {
+    if envoys.is_null() || n == 0 {
+        return;
+    }
+    for envoy in unsafe { std::slice::from_raw_parts_mut(envoys, n) } {
+        if !envoy.data.is_null() {
+            unsafe { free_envoy_in(registry, envoy.data.cast()) };
+            envoy.data = std::ptr::null_mut();
+        }
+    }
+}
+
+/// Frees a string returned by `receive_envoy`.
+///
+/// Also accepts pointers returned by `receive_envoy_bytes`.
+///
+/// # Safety
+/// * `envoy` must be a pointer returned by `receive_envoy`.
+/// * Must not be called more than once for the same pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn free_envoy(envoy: *mut c_char) {
+    if envoy.is_null() {
+        return;
+    }
+
+    let registry = match current_registry() {
+        Some(r) => r,
+        None => return, // Relations severed: the loan was reported as leaked
+    };
+
+    unsafe { free_envoy_in(&registry, envoy) }
+}
+
+/// Frees a string returned by `receive_envoy_ctx` on the same context.
+///
+/// # Safety
+/// * `ctx` must be a live pointer returned by `praborrow_context_new`.
+/// * `envoy` must be a pointer returned by `receive_envoy_ctx` for `ctx`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn free_envoy_ctx(ctx: *const Context, envoy: *mut c_char) {
+    if envoy.is_null() {
+        return;
+    }
+
+    if let Some(ctx) = unsafe { Context::from_ptr(ctx) } {
+        unsafe { free_envoy_in(ctx.registry(), envoy) }
+    }
+}
+
+/// Frees a buffer returned by `receive_envoy_bytes`.
+///
+/// # Safety
+/// * `envoy` must be a pointer returned by `receive_envoy_bytes`.
+/// * Must not be called more than once for the same pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn free_envoy_bytes(envoy: *mut u8) {
+    unsafe { free_envoy(envoy as *mut c_char) }
+}
+
+/// Frees a buffer returned by `receive_envoy_bytes_ctx` on the same context.
+///
+/// # Safety
+/// * `ctx` must be a live pointer returned by `praborrow_context_new`.
+/// * `envoy` must be a pointer returned by `receive_envoy_bytes_ctx` for `ctx`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn free_envoy_bytes_ctx(ctx: *const Context, envoy: *mut u8) {
+    unsafe { free_envoy_ctx(ctx, envoy as *mut c_char) }
+}
+
+/// Shared implementation of `free_envoy` and `free_envoy_ctx`.
+///
+/// # Safety
+/// See `free_envoy`.
+unsafe fn free_envoy_in(registry: &GlobalRegistry, envoy: *mut c_char) {
+    let ptr_val = envoy as usize;
+
+    // Check if we actually loaned this pointer
+    if let Some(loan) = registry.loans.return_pointer(ptr_val) {
+        // Safe to free: we created it and haven't freed it yet
+        // Retake ownership to drop it, matching how it was allocated
+        match loan.kind {
+            LoanKind::CString => unsafe {
+                let _ = CString::from_raw(envoy);
+            },
+            LoanKind::Bytes { alloc_len } => unsafe {
+                let slice = std::ptr::slice_from_raw_parts_mut(envoy as *mut u8, alloc_len);
+                let _ = Box::from_raw(slice);
+            },
+        }
+    } else {
+        // Double free or invalid pointer!
+        // We log error and DO NOT attempt to free, preventing segfault/heap corruption.
+        tracing::error!(
+            event = "ffi_violation",
+            ptr = ?envoy,
+            "Attempted to free invalid or already freed envoy pointer"
+        );
+    }
+}
+
+/// Registers a callback that receives envoys as Rust sends them.
+///
+/// While a callback is registered, envoys sent by Rust (`Diplomat::send`,
+/// reply handlers) are handed to it instead of being queued for
+/// `receive_envoy`. Envoys already queued are flushed to it before this
+/// call returns. Passing NULL unregisters.
+///
+/// # Threading and reentrancy
+/// * The callback runs synchronously on whichever thread sends the envoy,
+///   which may be a Rust thread or a foreign thread inside `send_envoy`.
+/// * It may call any other function of this library, including `send_envoy`.
+/// * Replacing or unregistering the callback waits for running invocations
+///   to finish, unless done from inside the callback itself.
+///
+/// # Returns
+/// * `0` - Success
+/// * `-15` - Registry not initialized
+///
+/// # Safety
+/// * `callback` must be safe to call from any thread with `user_data`.
+/// * `user_data` must stay valid until the callback is unregistered or
+///   relations are severed.
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(callback, user_data))]
+pub unsafe extern "C" fn register_envoy_callback(
+    callback: EnvoyCallbackFn,
+    user_data: *mut c_void,
+) -> c_int {
+    match current_registry() {
+        Some(registry) => register_envoy_callback_in(&registry, callback, user_data),
+        None => not_established("register_envoy_callback"),
+    }
+}
+
+/// Unregisters the envoy callback, returning to polled delivery.
+///
+/// # Returns
+/// * `0` - Success
+/// * `-15` - Registry not initialized
+#[unsafe(no_mangle)]
+#[tracing::instrument]
+pub extern "C" fn unregister_envoy_callback() -> c_int {
+    match current_registry() {
+        Some(registry) => register_envoy_callback_in(&registry, None, std::ptr::null_mut()),
+        None => not_established("unregister_envoy_callback"),
+    }
+}
+
+/// Registers an envoy callback on a specific context.
+///
+/// # Returns
+/// * `0` - Success
+/// * `-3` - `ctx` is NULL
+///
+/// # Safety
+/// * `ctx` must be a live pointer returned by `praborrow_context_new`.
+/// * See `register_envoy_callback` for `callback` and `user_data`.
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(ctx, callback, user_data))]
+pub unsafe extern "C" fn register_envoy_callback_ctx(
+    ctx: *const Context,
+    callback: EnvoyCallbackFn,
+    user_data: *mut c_void,
+) -> c_int {
+    match unsafe { Context::from_ptr(ctx) } {
+        Some(ctx) => register_envoy_callback_in(ctx.registry(), callback, user_data),
+        None => null_context("register_envoy_callback_ctx"),
+    }
+}
+
+/// Unregisters the envoy callback of a specific context.
+///
+/// # Returns
+/// * `0` - Success
+/// * `-3` - `ctx` is NULL
+///
+/// # Safety
+/// * `ctx` must be a live pointer returned by `praborrow_context_new`.
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(ctx))]
+pub unsafe extern "C" fn unregister_envoy_callback_ctx(ctx: *const Context) -> c_int {
+    unsafe { register_envoy_callback_ctx(ctx, None, std::ptr::null_mut()) }
+}
+
+/// Shared implementation of the callback registration functions.
+fn register_envoy_callback_in(
+    registry: &GlobalRegistry,
+    callback: EnvoyCallbackFn,
+    user_data: *mut c_void,
+) -> c_int {
+    let callback = callback.map(|func| EnvoyCallback { func, user_data });
+    tracing::info!(
+        event = "ffi_callback",
+        registered = callback.is_some(),
+        "Envoy callback updated"
+    );
+    registry.set_envoy_callback(callback);
+    SUCCESS
+}
+
+/// Registers a callback told about envoys that expire before being received.
+///
+/// Called as `callback(user_data, id, data, len)` for every envoy, in either
+/// direction, that a receiver skips because its TTL or deadline passed. It runs
+/// on the receiving thread; `data` is only valid during the call. Passing NULL
+/// unregisters. Replacing or unregistering waits for running invocations, as
+/// for `register_envoy_callback`.
+///
+/// # Returns
+/// * `0` - Success
+/// * `-15` - Registry not initialized
+///
+/// # Safety
+/// * `callback` must be safe to call from any thread with `user_data`.
+/// * `user_data` must stay valid until the callback is unregistered or
+///   relations are severed.
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(callback, user_data))]
+pub unsafe extern "C" fn register_expiry_callback(
+    callback: EnvoyCallbackFn,
+    user_data: *mut c_void,
+) -> c_int {
+    match current_registry() {
+        Some(registry) => register_expiry_callback_in(&registry, callback, user_data),
+        None => not_established("register_expiry_callback"),
+    }
+}
+
+/// Unregisters the expiry callback.
+///
+/// # Returns
+/// * `0` - Success
+/// * `-15` - Registry not initialized
+#[unsafe(no_mangle)]
+#[tracing::instrument]
+pub extern "C" fn unregister_expiry_callback() -> c_int {
+    match current_registry() {
+        Some(registry) => register_expiry_callback_in(&registry, None, std::ptr::null_mut()),
+        None => not_established("unregister_expiry_callback"),
+    }
+}
+
+/// Registers an expiry callback on a specific context.
+///
+/// # Returns
+/// * `0` - Success
+/// * `-3` - `ctx` is NULL
+///
+/// # Safety
+/// * `ctx` must be a live pointer returned by `praborrow_context_new`.
+/// * See `register_expiry_callback` for `callback` and `user_data`.
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(ctx, callback, user_data))]
+pub unsafe extern "C" fn register_expiry_callback_ctx(
+    ctx: *const Context,
+    callback: EnvoyCallbackFn,
+    user_data: *mut c_void,
+) -> c_int {
+    match unsafe { Context::from_ptr(ctx) } {
+        Some(ctx) => register_expiry_callback_in(ctx.registry(), callback, user_data),
+        None => null_context("register_expiry_callback_ctx"),
+    }
+}
+
+/// Unregisters the expiry callback of a specific context.
+///
+/// # Returns
+/// * `0` - Success
+/// * `-3` - `ctx` is NULL
+///
+/// # Safety
+/// * `ctx` must be a live pointer returned by `praborrow_context_new`.
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(ctx))]
+pub unsafe extern "C" fn unregister_expiry_callback_ctx(ctx: *const Context) -> c_int {
+    unsafe { register_expiry_callback_ctx(ctx, None, std::ptr::null_mut()) }
+}
+
+/// Shared implementation of the expiry callback registration functions.
+fn register_expiry_callback_in(
+    registry: &GlobalRegistry,
+    callback: EnvoyCallbackFn,
+    user_data: *mut c_void,
+) -> c_int {
+    let callback = callback.map(|func| EnvoyCallback { func, user_data });
+    tracing::info!(
+        event = "ffi_expiry_callback",
+        registered = callback.is_some(),
+        "Expiry callback updated"
+    );
+    registry.expiry.set_callback(callback);
+    SUCCESS
+}
+
+/// Returns a descriptor that is readable while the outbox holds envoys.
+///
+/// Register it with `epoll`, `poll` or libuv and call `receive_envoy` when it
+/// becomes readable; it stops being readable once the outbox is drained. Do
+/// not read from it. `sever_relations` closes the descriptor, and its number
+/// may then be reused by the next file opened, so remove it from the event
+/// loop before severing.
+///
+/// # Returns
+/// * `>= 0` - The descriptor
+/// * `-9` - Not supported on this platform, or the descriptor could not be created
+/// * `-15` - Registry not initialized
+#[unsafe(no_mangle)]
+#[tracing::instrument]
+pub extern "C" fn praborrow_outbox_fd() -> c_int {
+    match current_registry() {
+        Some(registry) => outbox_fd_in(&registry, "praborrow_outbox_fd"),
+        None => not_established("praborrow_outbox_fd"),
+    }
+}
+
+/// Returns a descriptor that is readable while the outbox of `ctx` holds envoys.
+///
+/// The descriptor hangs up when the context is severed and is closed when the
+/// context is freed.
+///
+/// # Returns
+/// * `-3` - `ctx` is NULL
+/// * Otherwise as `praborrow_outbox_fd`
+///
+/// # Safety
+/// * `ctx` must be a live pointer returned by `praborrow_context_new`.
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(ctx))]
+pub unsafe extern "C" fn praborrow_outbox_fd_ctx(ctx: *const Context) -> c_int {
+    match unsafe { Context::from_ptr(ctx) } {
+        Some(ctx) => outbox_fd_in(ctx.registry(), "praborrow_outbox_fd_ctx"),
+        None => null_context("praborrow_outbox_fd_ctx"),
+    }
+}
+
+/// Shared implementation of `praborrow_outbox_fd` and `praborrow_outbox_fd_ctx`.
+fn outbox_fd_in(registry: &GlobalRegistry, function: &'static str) -> c_int {
+    #[cfg(unix)]
+    if let Some(fd) = registry.outbox.ready_fd() {
+        return fd;
+    }
+    #[cfg(not(unix))]
+    let _ = registry;
+    last_error::fail(
+        ERR_FD_UNAVAILABLE,
+        function,
+        None,
+        "no readiness descriptor on this platform",
+    )
+}
+
+/// Copies the overflow counters of the default context into `out`.
+///
+/// # Returns
+/// * `0` - Success
+/// * `-3` - `out` is NULL
+/// * `-15` - Registry not initialized
+///
+/// # Safety
+/// * `out` must be NULL or point to writable memory for a `praborrow_overflow_report`.
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(out))]
+pub unsafe extern "C" fn praborrow_get_overflow_report(out: *mut OverflowReport) -> c_int {
+    match current_registry() {
+        Some(registry) => unsafe {
+            overflow_report_in(&registry, "praborrow_get_overflow_report", out)
+        },
+        None => not_established("praborrow_get_overflow_report"),
+    }
+}
+
+/// Copies the overflow counters of `ctx` into `out`.
+///
+/// # Returns
+/// * `-3` - `ctx` or `out` is NULL
+/// * Otherwise as `praborrow_get_overflow_report`
+///
+/// # Safety
+/// * `ctx` must be a live pointer returned by `praborrow_context_new`.
+/// * `out` must be NULL or point to writable memory for a `praborrow_overflow_report`.
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(ctx, out))]
+pub unsafe extern "C" fn praborrow_get_overflow_report_ctx(
+    ctx: *const Context,
+    out: *mut OverflowReport,
+) -> c_int {
+    match unsafe { Context::from_ptr(ctx) } {
+        Some(ctx) => unsafe {
+            overflow_report_in(ctx.registry(), "praborrow_get_overflow_report_ctx", out)
+        },
+        None => null_context("praborrow_get_overflow_report_ctx"),
+    }
+}
+
+/// Shared implementation of `praborrow_get_overflow_report` and `praborrow_get_overflow_report_ctx`.
+///
+/// # Safety
+/// * `out` must be NULL or point to writable memory for an `OverflowReport`.
+unsafe fn overflow_report_in(
+    registry: &GlobalRegistry,
+    function: &'static str,
+    out: *mut OverflowReport,
+) -> c_int {
+    if out.is_null() {
+        return last_error::fail(ERR_NULL_PTR, function, None, "out is NULL");
+    }
+    unsafe { out.write(registry.overflow_report()) };
+    SUCCESS
+}
+
+/// Copies a statistics snapshot of the default context into `out`.
+///
+/// Depth, bytes and loans are current values; the other counters count since
+/// the context was created, except `rejects`, which covers failed calls of the
+/// whole process.
+///
+/// # Returns
+/// * `0` - Success
+/// * `-3` - `out` is NULL
+/// * `-15` - Registry not initialized
+///
+/// # Safety
+/// * `out` must be NULL or point to writable memory for a `praborrow_stats`.
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(out))]
+pub unsafe extern "C" fn praborrow_get_stats(out: *mut DiplomacyStats) -> c_int {
+    match current_registry() {
+        Some(registry) => unsafe { stats_in(&registry, "praborrow_get_stats", out) },
+        None => not_established("praborrow_get_stats"),
+    }
+}
+
+/// Copies a statistics snapshot of `ctx` into `out`.
+///
+/// # Returns
+/// * `-3` - `ctx` or `out` is NULL
+/// * Otherwise as `praborrow_get_stats`
+///
+/// # Safety
+/// * `ctx` must be a live pointer returned by `praborrow_context_new`.
+/// * `out` must be NULL or point to writable memory for a `praborrow_stats`.
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(ctx, out))]
+pub unsafe extern "C" fn praborrow_get_stats_ctx(
+    ctx: *const Context,
+    out: *mut DiplomacyStats,
+) -> c_int {
+    match unsafe { Context::from_ptr(ctx) } {
+        Some(ctx) => unsafe { stats_in(ctx.registry(), "praborrow_get_stats_ctx", out) },
+        None => null_context("praborrow_get_stats_ctx"),
+    }
+}
+
+/// Shared implementation of `praborrow_get_stats` and `praborrow_get_stats_ctx`.
+///
+/// # Safety
+/// * `out` must be NULL or point to writable memory for a `DiplomacyStats`.
+unsafe fn stats_in(
+    registry: &GlobalRegistry,
+    function: &'static str,
+    out: *mut DiplomacyStats,
+) -> c_int {
+    if out.is_null() {
+        return last_error::fail(ERR_NULL_PTR, function, None, "out is NULL");
+    }
+    unsafe { out.write(registry.stats()) };
+    SUCCESS
+}
+
+/// Lists the envoys of the default context that the foreign side has not
+/// returned with `free_envoy`, `free_envoy_bytes` or `envoy_release`.
+///
+/// Fills `summary` with the totals and writes up to `max` entries, oldest
+/// first, to `out`. `out` may be NULL with `max` 0 to get the totals only.
+///
+/// # Returns
+/// * `>= 0` - Number of entries written to `out`
+/// * `-3` - `summary` is NULL, or `out` is NULL with a non-zero `max`
+/// * `-15` - Registry not initialized
+///
+/// # Safety
+/// * `summary` must be NULL or valid for writes.
+/// * `out` must be NULL or valid for writes of `max` elements.
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(summary, out))]
+pub unsafe extern "C" fn praborrow_outstanding_loans(
+    summary: *mut LoanSummary,
+    out: *mut LoanInfo,
+    max: usize,
+) -> c_int {
+    match current_registry() {
+        Some(registry) => unsafe {
+            outstanding_loans_in(&registry, "praborrow_outstanding_loans", summary, out, max)
+        },
+        None => not_established("praborrow_outstanding_loans"),
+    }
+}
+
+/// Lists the envoys of `ctx` that the foreign side has not returned.
+///
+/// # Returns
+/// As `praborrow_outstanding_loans`, or `-3` if `ctx` is NULL.
+///
+/// # Safety
+/// * `ctx` must be a live pointer returned by `praborrow_context_new`.
+/// * `summary` must be NULL or valid for writes.
+/// * `out` must be NULL or valid for writes of `max` elements.
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(ctx, summary, out))]
+pub unsafe extern "C" fn praborrow_outstanding_loans_ctx(
+    ctx: *const Context,
+    summary: *mut LoanSummary,
+    out: *mut LoanInfo,
+    max: usize,
+) -> c_int {
+    match unsafe { Context::from_ptr(ctx) } {
+        Some(ctx) => unsafe {
+            outstanding_loans_in(
+                ctx.registry(),
+                "praborrow_outstanding_loans_ctx",
+                summary,
+                out,
+                max,
+            )
+        },
+        None => null_context("praborrow_outstanding_loans_ctx"),
+    }
+}
+
+/// Shared implementation of `praborrow_outstanding_loans` and `praborrow_outstanding_loans_ctx`.
+///
+/// # Safety
+/// See `praborrow_outstanding_loans`.
+unsafe fn outstanding_loans_in(
+    registry: &GlobalRegistry,
+    function: &'static str,
+    summary: *mut LoanSummary,
+    out: *mut LoanInfo,
+    max: usize,
+) -> c_int {
+    if summary.is_null() {
+        return last_error::fail(ERR_NULL_PTR, function, None, "summary is NULL");
+    }
+    if out.is_null() && max > 0 {
+        return last_error::fail(
+            ERR_NULL_PTR,
+            function,
+            None,
+            format_args!("out is NULL with max {max}"),
+        );
+    }
+
+    let report = registry.loans.report();
+    unsafe { summary.write(report.summary) };
+    let written = report.loans.len().min(max).min(c_int::MAX as usize);
+    for (i, loan) in report.loans.into_iter().take(written).enumerate() {
+        unsafe { out.add(i).write(loan) };
+    }
+    written as c_int
+}
+
+/// Calls into Rust and waits for the reply.
+///
+/// The request is served by a Rust thread running `Diplomat::serve`, and
+/// matched to its reply by a generated correlation id. A reply that arrives
+/// after `timeout_ms` is discarded. `reply->data` is on loan to the caller and
+/// must be released with `free_envoy_bytes`.
+///
+/// # Arguments
+/// * `id` - Identifier of the request, repeated in the reply
+/// * `data` / `len` - Request payload; `data` may be NULL if `len` is 0
+/// * `timeout_ms` - Maximum wait, `UINT32_MAX` to wait indefinitely
+/// * `reply` - Out-parameter receiving the reply
+///
+/// # Returns
+/// * `0` - Success, `reply` filled
+/// * `-3` - `reply` is NULL, or `data` is NULL with a non-zero `len`
+/// * `-5` - `id` is 0
+/// * `-6` - The Rust handler panicked
+/// * `-7` - Too many calls waiting to be served
+/// * `-8` - Relations were severed before the reply
+/// * `-11` - No reply within `timeout_ms`
+/// * `-15` - Registry not initialized
+///
+/// On failure `reply` is zeroed.
+///
+/// # Safety
+/// * `data` must be valid for reads of `len` bytes if `len` is non-zero.
+/// * `reply` must be NULL or valid for writes.
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(data, reply))]
+pub unsafe extern "C" fn praborrow_call(
+    id: u32,
+    data: *const u8,
+    len: usize,
+    timeout_ms: u32,
+    reply: *mut Envoy,
+) -> c_int {
+    match current_registry() {
+        Some(registry) => unsafe {
+            call_in(
+                &registry,
+                "praborrow_call",
+                id,
+                data,
+                len,
+                timeout_ms,
+                reply,
+            )
+        },
+        None => not_established("praborrow_call"),
+    }
+}
+
+/// Calls into Rust on a specific context and waits for the reply.
+///
+/// # Returns
+/// * `-3` - Additionally, `ctx` is NULL
+/// * Otherwise as `praborrow_call`
+///
+/// # Safety
+/// * `ctx` must be a live pointer returned by `praborrow_context_new`.
+/// * Otherwise as `praborrow_call`
+#[unsafe(no_mangle)]
+#[tracing::instrument(skip(ctx, data, reply))]
+pub unsafe extern "C" fn praborrow_call_ctx(
+    ctx: *const Context,
+    id: u32,
+    data: *const u8,
+    len: usize,
+    timeout_ms: u32,
+    reply: *mut Envoy,
+) -> c_int {
+    match unsafe { Context::from_ptr(ctx) } {
+        Some(ctx) => unsafe {
+            call_in(
+                ctx.registry(),
+                "praborrow_call_ctx",
+                id,
+                data,
+                len,
+                timeout_ms,
+                reply,
+            )
+        },
+        None => null_context("praborrow_call_ctx"),
+    }
+}
+
+/// Shared implementation of `praborrow_call` and `praborrow_call_ctx`.
+///
+/// # Safety
+/// * `data` must be valid for reads of `len` bytes if `len` is non-zero.
+/// * `reply` must be NULL or valid for writes.
+unsafe fn call_in(
+    registry: &GlobalRegistry,
+    function: &'static str,
+    id: u32,
+    data: *const u8,
+    len: usize,
+    timeout_ms: u32,
+    reply: *mut Envoy,
+) -> c_int {
+    if reply.is_null() {
+        tracing::error!("Received NULL reply pointer");
+        return last_error::fail(ERR_NULL_PTR, function, Some(id), "reply is NULL");
+    }
+    unsafe { reply.write(Envoy::empty()) };
+
+    if data.is_null() && len != 0 {
+        tracing::error!(envoy_id = id, len, "Received NULL payload");
+        return last_error::fail(
+            ERR_NULL_PTR,
+            function,
+            Some(id),
+            format_args!("data is NULL with len {len}"),
+        );
+    }
+    if id == 0 {
+        tracing::error!(envoy_id = 0, "Received Invalid ID 0");
+        return last_error::fail(ERR_INVALID_ID, function, Some(id), "id 0 is reserved");
+    }
+
+    let payload = if len == 0 {
+        Vec::new()
+    } else {
+        unsafe { std::slice::from_raw_parts(data, len) }.to_vec()
+    };
+
+    match registry.call(id, payload, deadline_after(timeout_ms)) {
+        Ok(envelope) => {
+            unsafe { reply.write(lend_envelope(registry, envelope)) };
+            SUCCESS
+        }
+        Err(e) => {
+            tracing::warn!(envoy_id = id, error = %e, "Call failed");
+            last_error::fail(PraborrowStatus::from(e).code(), function, Some(id), e)
+        }
+    }
+}
+
+/// Forwards the library's diagnostics at `min_level` or above to `callback`.
+///
+/// Called as `callback(level, target, message, ctx)` on whichever thread
+/// logs, for events such as the `ffi_violation` error of `free_envoy`.
+/// `target` and `message` are only valid during the call. Events logged
+/// while the callback runs on the same thread are dropped. Passing NULL
+/// unregisters. Works before relations are established.
+///
+/// The first call installs a global `tracing` subscriber. In a process that
+/// already has one, events reach the callback only if it includes
+/// `praborrow_diplomacy::logging::layer()`.
+///
+/// # Returns
+/// * `0` - Success
+/// * `-10` - `min_level` is not a `PRABORROW_LOG_LEVEL_*` value
+///
+/// # Safety
+/// * `callback` must be safe to call from any thread with `ctx`.
+/// * `ctx` must stay valid until the callback is replaced or unregistered;
+///   both wait for running invocations, unless done from inside the callback.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn praborrow_set_log_callback(
+    callback: LogCallbackFn,
+    ctx: *mut c_void,
+    min_level: LogLevel,
+) -> c_int {
+    if !min_level.is_known() {
+        return unknown_log_level("praborrow_set_log_callback", min_level);
+    }
+    logging::set_callback(callback, ctx, min_level);
+    SUCCESS
+}
+
+/// Changes the least severe level forwarded to the log callback.
+///
+/// `PRABORROW_LOG_LEVEL_OFF` silences the callback without unregistering it.
+///
+/// # Returns
+/// * `0` - Success
+/// * `-10` - `min_level` is not a `PRABORROW_LOG_LEVEL_*` value
+#[unsafe(no_mangle)]
+pub extern "C" fn praborrow_set_log_level(min_level: LogLevel) -> c_int {
+    if !min_level.is_known() {
+        return unknown_log_level("praborrow_set_log_level", min_level);
+    }
+    logging::set_min_level(min_level);
+    SUCCESS
+}
+
+/// Reports a minimum log level that names no level.
+fn unknown_log_level(function: &'static str, level: LogLevel) -> c_int {
+    last_error::fail(
+        ERR_INVALID_CONFIG,
+        function,
+        None,
+        format_args!("unknown log level {}", level.0),
+    )
+}
+
+/// Returns the code of the most recent failed call on the calling thread.
+///
+/// Like `errno`, it is only updated by calls that fail, so read it right
+/// after a call returns an error.
+///
+/// # Returns
+/// * The negative code of the last failure, or `0` if no call on this thread
+///   has failed.
+#[unsafe(no_mangle)]
+pub extern "C" fn praborrow_last_error_code() -> c_int {
+    last_error::code()
+}
+
+/// Describes the most recent failed call on the calling thread.
+///
+/// Copies a NUL-terminated message such as
+/// `send_envoy (envoy 7): invalid UTF-8 at byte 3` into `buf`, truncated to
+/// `len - 1` bytes. `buf` may be NULL with `len` 0 to ask for the length.
+///
+/// # Returns
+/// * `>= 0` - Length of the whole message without its NUL, like `snprintf`;
+///   `0` if no call on this thread has failed
+/// * `-3` - `buf` is NULL with a non-zero `len`
+///
+/// # Safety
+/// * `buf` must be NULL or valid for writes of `len` bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn praborrow_last_error_message(buf: *mut c_char, len: usize) -> c_int {
+    if buf.is_null() && len != 0 {
+        return ERR_NULL_PTR;
+    }
+    let message = last_error::message().unwrap_or_default();
+    if len != 0 {
+        // Cut at a character boundary so the copy stays valid UTF-8.
+        let mut end = message.len().min(len - 1);
+        while !message.is_char_boundary(end) {
+            end -= 1;
+        }
+        unsafe {
+            std::ptr::copy_nonoverlapping(message.as_ptr(), buf.cast::<u8>(), end);
+            *buf.add(end) = 0;
+        }
+    }
+    c_int::try_from(message.len()).unwrap_or(c_int::MAX)
+}
+
+/// Describes a status code returned by any function of this library.
+///
+/// # Returns
+/// * `const char*` - Static NUL-terminated description; never NULL. Codes
+///   that are no `praborrow_status` give "Unknown status".
+#[unsafe(no_mangle)]
+pub extern "C" fn praborrow_strerror(code: c_int) -> *const c_char {
+    PraborrowStatus::from_code(code)
+        .map_or(c"Unknown status", PraborrowStatus::description)
+        .as_ptr()
+}
+
+/// Version of the C ABI declared by `praborrow.h`, exported as
+/// `PRABORROW_ABI_VERSION`.
+///
+/// Bumped whenever an exported declaration changes, independently of the
+/// crate version.
+pub const ABI_VERSION: u32 = 6;
+
+/// Returns the version of the PraBorrow diplomacy crate.
+#[unsafe(no_mangle)]
+pub extern "C" fn praborrow_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr() as *const c_char
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Serializes the tests that use the process-wide session.
+    static GLOBAL_SESSION: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+    fn global_session() -> std::sync::MutexGuard<'static, ()> {
+        GLOBAL_SESSION
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    #[test]
+    fn test_diplomacy_flow() {
+        let _session = global_session();
+        // 1. Establish relations
+        // Note: tests run in parallel, so REGISTRY might already be set.
+        // We handle returns gracefully.
+        let status = establish_relations();
+        assert!(status == 0 || status == -1);
+        safe::Diplomat::set_reply_handler(EchoHandler).unwrap();
+
+        // 2. Send envoy (C -> Rust)
+        let msg = CString::new("Hello from C").unwrap();
+        let send_status = unsafe { send_envoy(101, msg.as_ptr()) };
+        assert_eq!(send_status, 0);
+
+        // 3. Receive envoy (Rust -> C) - should contain the Ack
+        let received_ptr = receive_envoy();
+        assert!(!received_ptr.is_null());
+
+        let received_str = unsafe { CStr::from_ptr(received_ptr).to_str().unwrap() };
+        assert_eq!(received_str, "Ack: Hello from C");
+
+        // 4. Free envoy
+        unsafe { free_envoy(received_ptr) };
+
+        // 5. Receive empty
+        let empty = receive_envoy();
+        assert!(empty.is_null());
+    }
+
+    #[test]
+    fn test_sever_discards_queues_and_reports_loans() {
+        // Severs a private registry so the shared session used by other tests is untouched.
+        let registry = GlobalRegistry::new();
+        assert!(registry.incoming.reserve(7, Priority::Normal));
+        registry
+            .incoming
+            .push(registry.seal(1, Routing::default(), b"pending".to_vec()));
+        for _ in 0..2 {
+            registry
+                .dispatch(1, Routing::default(), b"Ack: pending")
+                .unwrap();
+        }
+        let loan = Loan::new(LoanKind::CString, 1, 7);
+        registry.loans.lend_pointer(0xdead, loan);
+
+        let report = registry.sever();
+        assert_eq!(
+            report,
+            safe::SeveranceReport {
+                discarded_incoming: 1,
+                discarded_outbox: 2,
+                leaked_loans: 1,
+            }
+        );
+        assert!(registry.is_closed());
+        assert!(registry.incoming.pop().is_none());
+        assert!(registry.outbox.pop().is_none());
+    }
+
+    #[test]
+    fn test_global_session_can_be_severed_and_reestablished() {
+        let _session = global_session();
+        let _ = sever_relations();
+        assert_eq!(establish_relations(), SUCCESS);
+        let old = current_registry().unwrap();
+
+        // One envoy stays on loan, one stays queued for Rust.
+        safe::Diplomat::send(301, "kept on loan").unwrap();
+        let loaned = receive_envoy();
+        assert!(!loaned.is_null());
+        let queued = CString::new("left queued").unwrap();
+        assert_eq!(unsafe { send_envoy(302, queued.as_ptr()) }, SUCCESS);
+
+        assert_eq!(sever_relations(), 1);
+        assert_eq!(
+            deliver_envoy(
+                &old,
+                "send_envoy",
+                303,
+                Routing::default(),
+                b"late".to_vec()
+            ),
+            ERR_CLOSED
+        );
+        assert_eq!(
+            unsafe { send_envoy(303, queued.as_ptr()) },
+            ERR_NOT_INITIALIZED
+        );
+        assert!(receive_envoy().is_null());
+        assert_eq!(sever_relations(), ERR_NOT_INITIALIZED);
+
+        // The loaned pointer outlives the session that lent it.
+        assert_eq!(unsafe { CStr::from_ptr(loaned) }, c"kept on loan");
+        drop(unsafe { CString::from_raw(loaned) });
+
+        assert_eq!(establish_relations(), SUCCESS);
+        assert!(safe::Diplomat::receive().is_none());
+        safe::Diplomat::send(304, "fresh").unwrap();
+        assert_eq!(
+            safe::Diplomat::shutdown().unwrap(),
+            safe::SeveranceReport {
+                discarded_incoming: 0,
+                discarded_outbox: 1,
+                leaked_loans: 0,
+            }
+        );
+        assert!(matches!(
+            safe::Diplomat::shutdown(),
+            Err(safe::DiplomacyError::NotInitialized)
+        ));
+        assert!(matches!(
+            safe::Diplomat::send(305, "too late"),
+            Err(safe::DiplomacyError::NotInitialized)
+        ));
+    }
+
+    #[test]
+    fn test_contexts_are_isolated() {
+        let first = praborrow_context_new();
+        let second = praborrow_context_new();
+        let embassy = unsafe { safe::Embassy::from_context(first) }.unwrap();
+        embassy.set_reply_handler(EchoHandler);
+
+        let msg = CString::new("only for first").unwrap();
+        assert_eq!(unsafe { send_envoy_ctx(first, 7, msg.as_ptr()) }, 0);
+        assert!(unsafe { receive_envoy_ctx(second) }.is_null());
+
+        let received_ptr = unsafe { receive_envoy_ctx(first) };
+        assert!(!received_ptr.is_null());
+        let received_str = unsafe { CStr::from_ptr(received_ptr).to_str().unwrap() };
+        assert_eq!(received_str, "Ack: only for first");
+
+        // A loan from one context cannot be returned to another.
+        unsafe { free_envoy_ctx(second, received_ptr) };
+        unsafe { free_envoy_ctx(first, received_ptr) };
+
+        assert_eq!(embassy.receive().as_deref(), Some("only for first"));
+
+        assert_eq!(unsafe { praborrow_context_free(first) }, 0);
+        assert_eq!(unsafe { praborrow_context_free(second) }, 0);
+        assert_eq!(
+            embassy.send(1, "too late"),
+            Err(safe::DiplomacyError::Closed)
+        );
+    }
+
+    #[test]
+    fn test_binary_envoy_roundtrip() {
+        let ctx = praborrow_context_new();
+        let embassy = unsafe { safe::Embassy::from_context(ctx) }.unwrap();
+        embassy.set_reply_handler(EchoHandler);
+        let blob = [0x08, 0x00, 0xff, 0x00, 0x2a];
+
+        let status = unsafe { send_envoy_bytes_ctx(ctx, 3, blob.as_ptr(), blob.len()) };
+        assert_eq!(status, 0);
+
+        let mut len = 0;
+        let ptr = unsafe { receive_envoy_bytes_ctx(ctx, &mut len) };
+        assert!(!ptr.is_null());
+        let received = unsafe { std::slice::from_raw_parts(ptr, len) };
+        assert_eq!(received, [b"Ack: ".as_slice(), &blob].concat());
+        unsafe { free_envoy_bytes_ctx(ctx, ptr) };
+
+        // NULL is accepted for an empty payload.
+        assert_eq!(
+            unsafe { send_envoy_bytes_ctx(ctx, 4, std::ptr::null(), 0) },
+            0
+        );
+        let ptr = unsafe { receive_envoy_bytes_ctx(ctx, &mut len) };
+        assert!(!ptr.is_null());
+        assert_eq!(len, 5);
+        unsafe { free_envoy_bytes_ctx(ctx, ptr) };
+
+        assert_eq!(embassy.receive_bytes(), Some(blob.to_vec()));
+
+        // A C string cannot carry the blob; it waits for a binary receive.
+        embassy.send_bytes(5, &blob).unwrap();
+        assert!(unsafe { receive_envoy_ctx(ctx) }.is_null());
+        assert_eq!(praborrow_last_error_code(), ERR_CONTAINS_NUL);
+        assert!(unsafe { receive_envoy_timeout_ctx(ctx, 0) }.is_null());
+        let mut message = [0 as c_char; 64];
+        unsafe { praborrow_last_error_message(message.as_mut_ptr(), message.len()) };
+        let message = unsafe { CStr::from_ptr(message.as_ptr()) };
+        assert!(
+            message
+                .to_bytes()
+                .starts_with(b"receive_envoy_timeout_ctx ")
+        );
+        let ptr = unsafe { receive_envoy_bytes_ctx(ctx, &mut len) };
+        assert_eq!(unsafe { std::slice::from_raw_parts(ptr, len) }, blob);
+        unsafe { free_envoy_bytes_ctx(ctx, ptr) };
+        assert_eq!(unsafe { praborrow_context_free(ctx) }, 0);
+    }
+
+    #[test]
+    fn test_envelope_metadata_crosses_boundary() {
+        let embassy = safe::Embassy::new();
+        let ctx = embassy.context();
+
+        embassy.send(21, "first").unwrap();
+        embassy.send(22, "second").unwrap();
+
+        let mut first = Envoy::empty();
+        let mut second = Envoy::empty();
+        assert_eq!(
+            unsafe { receive_envoy_envelope_ctx(ctx, &mut first) },
+            SUCCESS
+        );
+        assert_eq!(
+            unsafe { receive_envoy_envelope_ctx(ctx, &mut second) },
+            SUCCESS
+        );
+        assert_eq!((first.id, second.id), (21, 22));
+        assert!(second.sequence > first.sequence);
+        assert!(first.timestamp_us > 0);
+        let payload = unsafe { std::slice::from_raw_parts(first.data, first.len) };
+        assert_eq!(payload, b"first");
+        unsafe { free_envoy_bytes_ctx(ctx, first.data) };
+        unsafe { free_envoy_bytes_ctx(ctx, second.data) };
+
+        let mut none = Envoy::empty();
+        assert_eq!(unsafe { receive_envoy_envelope_ctx(ctx, &mut none) }, EMPTY);
+        assert!(none.data.is_null());
+
+        let msg = CString::new("from C").unwrap();
+        assert_eq!(unsafe { send_envoy_ctx(ctx, 9, msg.as_ptr()) }, 0);
+        let envelope = embassy.receive_envelope().unwrap();
+        assert_eq!(envelope.id, 9);
+        assert_eq!(envelope.payload, b"from C");
+
+        assert_eq!(unsafe { praborrow_context_free(ctx) }, 0);
+    }
+
+    #[test]
+    fn test_reply_handler_decides_replies() {
+        let embassy = safe::Embassy::new();
+        let ctx = embassy.context();
+        let msg = CString::new("ping").unwrap();
+
+        // Without a handler nothing is echoed back.
+        assert_eq!(unsafe { send_envoy_ctx(ctx, 1, msg.as_ptr()) }, 0);
+        assert!(unsafe { receive_envoy_ctx(ctx) }.is_null());
+        assert_eq!(embassy.receive().as_deref(), Some("ping"));
+
+        embassy
+            .set_reply_handler(|envelope: &Envelope| (envelope.id == 2).then(|| b"pong".to_vec()));
+        assert_eq!(unsafe { send_envoy_ctx(ctx, 1, msg.as_ptr()) }, 0);
+        assert!(unsafe { receive_envoy_ctx(ctx) }.is_null());
+        assert_eq!(unsafe { send_envoy_ctx(ctx, 2, msg.as_ptr()) }, 0);
+        let reply = unsafe { receive_envoy_ctx(ctx) };
+        assert_eq!(unsafe { CStr::from_ptr(reply) }.to_str().unwrap(), "pong");
+        unsafe { free_envoy_ctx(ctx, reply) };
+
+        // A panicking handler does not fail the foreign call.
+        embassy.set_reply_handler(|_: &Envelope| -> Option<Vec<u8>> { panic!("handler bug") });
+        assert_eq!(unsafe { send_envoy_ctx(ctx, 3, msg.as_ptr()) }, 0);
+        assert!(unsafe { receive_envoy_ctx(ctx) }.is_null());
+
+        embassy.clear_reply_handler();
+        assert_eq!(unsafe { praborrow_context_free(ctx) }, 0);
+    }
+
+    type SeenEnvoys = std::sync::Mutex<Vec<(u32, Vec<u8>)>>;
+
+    unsafe extern "C" fn collect_envoy(
+        user_data: *mut c_void,
+        id: u32,
+        data: *const u8,
+        len: usize,
+    ) {
+        let seen = unsafe { &*(user_data as *const SeenEnvoys) };
+        let payload = unsafe { std::slice::from_raw_parts(data, len) }.to_vec();
+        seen.lock().unwrap().push((id, payload));
+    }
+
+    #[test]
+    fn test_envoy_callback_receives_pushed_envoys() {
+        let embassy = safe::Embassy::new();
+        let ctx = embassy.context();
+        let seen = SeenEnvoys::default();
+        let user_data = &seen as *const _ as *mut c_void;
+
+        // Queued before registration: flushed on register.
+        embassy.send(1, "queued").unwrap();
+        let status = unsafe { register_envoy_callback_ctx(ctx, Some(collect_envoy), user_data) };
+        assert_eq!(status, 0);
+        embassy.send(2, "pushed").unwrap();
+        assert!(unsafe { receive_envoy_ctx(ctx) }.is_null());
+
+        assert_eq!(unsafe { unregister_envoy_callback_ctx(ctx) }, 0);
+        embassy.send(3, "polled").unwrap();
+
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![(1, b"queued".to_vec()), (2, b"pushed".to_vec())]
+        );
+        let polled = unsafe { receive_envoy_ctx(ctx) };
+        assert_eq!(
+            unsafe { CStr::from_ptr(polled) }.to_str().unwrap(),
+            "polled"
+        );
+        unsafe { free_envoy_ctx(ctx, polled) };
+
+        assert_eq!(unsafe { praborrow_context_free(ctx) }, 0);
+    }
+
+    #[test]
+    fn test_blocking_receive_wakes_on_send_and_shutdown() {
+        let embassy = safe::Embassy::new();
+        let ctx = embassy.context() as usize;
+
+        let receiver = std::thread::spawn(move || {
+            let ptr = unsafe { receive_envoy_timeout_ctx(ctx as *const Context, u32::MAX) };
+            let text = unsafe { CStr::from_ptr(ptr) }.to_str().unwrap().to_owned();
+            unsafe { free_envoy_ctx(ctx as *const Context, ptr) };
+            text
+        });
+        std::thread::sleep(Duration::from_millis(20));
+        embassy.send(1, "wake up").unwrap();
+        assert_eq!(receiver.join().unwrap(), "wake up");
+
+        assert!(unsafe { receive_envoy_timeout_ctx(ctx as *const Context, 10) }.is_null());
+        assert_eq!(
+            embassy.recv_timeout(Duration::from_millis(10)),
+            Err(safe::DiplomacyError::Timeout)
+        );
+
+        let waiter = {
+            let embassy = embassy.clone();
+            std::thread::spawn(move || embassy.recv_blocking())
+        };
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(unsafe { praborrow_context_free(ctx as *mut Context) }, 0);
+        assert_eq!(waiter.join().unwrap(), Err(safe::DiplomacyError::Closed));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_readiness_fd_tracks_queue_state() {
+        let embassy = safe::Embassy::new();
+        let ctx = embassy.context();
+        let registry = Arc::clone(unsafe { Context::from_ptr(ctx) }.unwrap().registry());
+
+        embassy.send(1, "already queued").unwrap();
+        assert!(unsafe { praborrow_outbox_fd_ctx(ctx) } >= 0);
+        assert!(registry.outbox.is_ready_signalled());
+
+        embassy.send(2, "second").unwrap();
+        for _ in 0..2 {
+            assert!(registry.outbox.is_ready_signalled());
+            let ptr = unsafe { receive_envoy_ctx(ctx) };
+            unsafe { free_envoy_ctx(ctx, ptr) };
+        }
+        assert!(!registry.outbox.is_ready_signalled());
+
+        assert!(embassy.incoming_fd().is_ok());
+        assert!(!registry.incoming.is_ready_signalled());
+        let msg = CString::new("for rust").unwrap();
+        assert_eq!(unsafe { send_envoy_ctx(ctx, 3, msg.as_ptr()) }, 0);
+        assert!(registry.incoming.is_ready_signalled());
+        embassy.receive().unwrap();
+        assert!(!registry.incoming.is_ready_signalled());
+
+        // Severing hangs both descriptors up while the context is still alive.
+        embassy.send(4, "discarded").unwrap();
+        let fds = [
+            unsafe { praborrow_outbox_fd_ctx(ctx) },
+            embassy.incoming_fd().unwrap(),
+        ];
+        embassy.shutdown();
+        for fd in fds {
+            use std::io::Read;
+            use std::os::fd::BorrowedFd;
+            let fd = unsafe { BorrowedFd::borrow_raw(fd) };
+            let mut stream = std::os::unix::net::UnixStream::from(fd.try_clone_to_owned().unwrap());
+            let mut byte = [0u8];
+            // A signal written before the sever may still be pending.
+            let mut read = stream.read(&mut byte).unwrap();
+            if read == 1 {
+                read = stream.read(&mut byte).unwrap();
+            }
+            assert_eq!(read, 0);
+        }
+
+        assert_eq!(unsafe { praborrow_context_free(ctx) }, 0);
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_async_stream_and_backpressured_send() {
+        use futures_core::Stream;
+        use std::sync::atomic::AtomicUsize;
+        use std::task::{Poll, Wake, Waker};
+
+        #[derive(Default)]
+        struct CountingWaker(AtomicUsize);
+        impl Wake for CountingWaker {
+            fn wake(self: Arc<Self>) {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let counter = Arc::new(CountingWaker::default());
+        let waker = Waker::from(Arc::clone(&counter));
+        let mut cx = std::task::Context::from_waker(&waker);
+
+        let embassy = safe::Embassy::new();
+        let ctx = embassy.context();
+
+        // Stream: pending until C sends, then woken.
+        let mut incoming = std::pin::pin!(embassy.incoming());
+        assert!(incoming.as_mut().poll_next(&mut cx).is_pending());
+        let msg = CString::new("async hello").unwrap();
+        assert_eq!(unsafe { send_envoy_ctx(ctx, 5, msg.as_ptr()) }, 0);
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+        match incoming.as_mut().poll_next(&mut cx) {
+            Poll::Ready(Some(envelope)) => assert_eq!(envelope.payload, b"async hello"),
+            other => panic!("expected an envelope, got {other:?}"),
+        }
+
+        // Send: waits for capacity instead of failing.
+        while embassy.send(1, "filler").is_ok() {}
+        let mut send = std::pin::pin!(embassy.send_async(2, "patient"));
+        assert!(send.as_mut().poll(&mut cx).is_pending());
+        let ptr = unsafe { receive_envoy_ctx(ctx) };
+        unsafe { free_envoy_ctx(ctx, ptr) };
+        assert_eq!(counter.0.load(Ordering::SeqCst), 2);
+        assert_eq!(send.as_mut().poll(&mut cx), Poll::Ready(Ok(())));
+
+        // Shutdown ends the stream.
+        assert!(incoming.as_mut().poll_next(&mut cx).is_pending());
+        assert_eq!(unsafe { praborrow_context_free(ctx) }, 0);
+        assert!(matches!(
+            incoming.as_mut().poll_next(&mut cx),
+            Poll::Ready(None)
+        ));
+    }
+
+    #[test]
+    fn test_configured_limits_bound_depth_and_bytes() {
+        let config = DiplomacyConfig::new()
+            .incoming_depth(2)
+            .incoming_bytes(usize::MAX)
+            .outbox_bytes(8);
+        let embassy = safe::Embassy::with_config(config).unwrap();
+        let ctx = embassy.context();
+        let msg = CString::new("x").unwrap();
+
+        assert_eq!(unsafe { send_envoy_ctx(ctx, 1, msg.as_ptr()) }, SUCCESS);
+        assert_eq!(unsafe { send_envoy_ctx(ctx, 2, msg.as_ptr()) }, SUCCESS);
+        assert_eq!(
+            unsafe { send_envoy_ctx(ctx, 3, msg.as_ptr()) },
+            ERR_QUEUE_FULL
+        );
+
+        embassy.send(1, "12345").unwrap();
+        assert_eq!(
+            embassy.send(2, "6789"),
+            Err(safe::DiplomacyError::QueueFull)
+        );
+        embassy.send(3, "678").unwrap();
+        let ptr = unsafe { receive_envoy_ctx(ctx) };
+        unsafe { free_envoy_ctx(ctx, ptr) };
+        embassy.send(4, "12345").unwrap();
+
+        let invalid = DiplomacyConfig::new().outbox_depth(0);
+        assert!(unsafe { praborrow_context_new_with_config(&invalid) }.is_null());
+        assert_eq!(unsafe { praborrow_context_free(ctx) }, 0);
+    }
+
+    #[test]
+    fn test_overflow_policies() {
+        let config = DiplomacyConfig::new()
+            .incoming_depth(2)
+            .incoming_overflow(OverflowPolicy::DROP_OLDEST)
+            .outbox_depth(1)
+            .outbox_overflow(OverflowPolicy::SPILL);
+        let embassy = safe::Embassy::with_config(config).unwrap();
+        let ctx = embassy.context();
+        let msg = CString::new("x").unwrap();
+
+        for id in 1..=3 {
+            assert_eq!(unsafe { send_envoy_ctx(ctx, id, msg.as_ptr()) }, SUCCESS);
+        }
+        assert_eq!(embassy.receive_envelope().unwrap().id, 2);
+        assert_eq!(embassy.receive_envelope().unwrap().id, 3);
+
+        // Only envoys of the same or a lower priority make room.
+        for id in 4..=5 {
+            let status = unsafe { send_envoy_priority_ctx(ctx, id, 2, msg.as_ptr()) };
+            assert_eq!(status, SUCCESS);
+        }
+        assert_eq!(
+            unsafe { send_envoy_priority_ctx(ctx, 6, 0, msg.as_ptr()) },
+            ERR_QUEUE_FULL
+        );
+        assert_eq!(
+            unsafe { send_envoy_priority_ctx(ctx, 7, 2, msg.as_ptr()) },
+            SUCCESS
+        );
+        assert_eq!(embassy.receive_envelope().unwrap().id, 5);
+        assert_eq!(embassy.receive_envelope().unwrap().id, 7);
+
+        let spilled = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = Arc::clone(&spilled);
+        embassy.set_spill_handler(move |direction: Direction, envelope: Envelope| {
+            sink.lock().unwrap().push((direction, envelope.id));
+        });
+        embassy.send(1, "a").unwrap();
+        embassy.send(2, "b").unwrap();
+        assert_eq!(*spilled.lock().unwrap(), [(Direction::Outbox, 2)]);
+        embassy.clear_spill_handler();
+        assert_eq!(embassy.send(3, "c"), Err(safe::DiplomacyError::QueueFull));
+
+        let mut report = OverflowReport::default();
+        assert_eq!(
+            unsafe { praborrow_get_overflow_report_ctx(ctx, &mut report) },
+            SUCCESS
+        );
+        assert_eq!(report, embassy.overflow_report());
+        assert_eq!(report.incoming.dropped_oldest, 2);
+        assert_eq!(report.incoming.rejected, 1);
+        assert_eq!(report.outbox.spilled, 1);
+        assert_eq!(report.outbox.rejected, 1);
+        assert_eq!(unsafe { praborrow_context_free(ctx) }, 0);
+
+        // Blocked senders wait for room, then give up at the timeout.
+        let config = DiplomacyConfig::new()
+            .outbox_depth(1)
+            .outbox_overflow(OverflowPolicy::BLOCK)
+            .outbox_block_timeout(Duration::from_millis(20));
+        let embassy = safe::Embassy::with_config(config).unwrap();
+        embassy.send(1, "a").unwrap();
+        assert_eq!(embassy.send(2, "b"), Err(safe::DiplomacyError::QueueFull));
+        assert_eq!(embassy.overflow_report().outbox.timed_out, 1);
+
+        let config = config.outbox_block_timeout(Duration::from_secs(30));
+        let embassy = safe::Embassy::with_config(config).unwrap();
+        let ctx = embassy.context() as usize;
+        embassy.send(1, "a").unwrap();
+        let receiver = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            let ctx = ctx as *const Context;
+            let ptr = unsafe { receive_envoy_ctx(ctx) };
+            unsafe { free_envoy_ctx(ctx, ptr) };
+        });
+        embassy.send(2, "b").unwrap();
+        receiver.join().unwrap();
+        assert_eq!(unsafe { praborrow_context_free(ctx as *mut Context) }, 0);
+
+        let invalid = DiplomacyConfig::new().incoming_overflow(OverflowPolicy(9));
+        assert!(unsafe { praborrow_context_new_with_config(&invalid) }.is_null());
+    }
+
+    #[test]
+    fn test_named_channels_are_isolated() {
+        let embassy = safe::Embassy::new();
+        let ctx = embassy.context();
+        let name = CString::new("metrics").unwrap();
+        let config = DiplomacyConfig::new().incoming_depth(1);
+        let metrics =
+            unsafe { praborrow_channel_open_with_config_ctx(ctx, name.as_ptr(), &config) };
+        assert!(!metrics.is_null());
+
+        let msg = CString::new("cpu=3").unwrap();
+        assert_eq!(unsafe { send_envoy_ctx(metrics, 1, msg.as_ptr()) }, SUCCESS);
+        assert_eq!(
+            unsafe { send_envoy_ctx(metrics, 2, msg.as_ptr()) },
+            ERR_QUEUE_FULL
+        );
+        assert_eq!(unsafe { send_envoy_ctx(ctx, 3, msg.as_ptr()) }, SUCCESS);
+
+        // The same name reaches the same channel; the parent keeps its own queues.
+        let channel = embassy.channel("metrics").unwrap();
+        assert_eq!(channel.receive_envelope().unwrap().id, 1);
+        assert_eq!(embassy.receive_envelope().unwrap().id, 3);
+        assert!(embassy.receive_envelope().is_none());
+
+        channel.send(4, "flush").unwrap();
+        assert!(unsafe { receive_envoy_ctx(ctx) }.is_null());
+        let ptr = unsafe { receive_envoy_ctx(metrics) };
+        assert_eq!(unsafe { CStr::from_ptr(ptr) }.to_str().unwrap(), "flush");
+        unsafe { free_envoy_ctx(metrics, ptr) };
+
+        assert!(unsafe { praborrow_channel_open_ctx(ctx, std::ptr::null()) }.is_null());
+
+        channel.send(5, "pending").unwrap();
+        let report = embassy.shutdown();
+        assert_eq!(report.discarded_outbox, 1);
+        assert_eq!(channel.send(6, "late"), Err(safe::DiplomacyError::Closed));
+        assert_eq!(unsafe { praborrow_context_free(metrics) }, 0);
+        assert_eq!(
+            embassy.channel("metrics").err(),
+            Some(safe::DiplomacyError::Closed)
+        );
+        assert_eq!(unsafe { praborrow_context_free(ctx) }, 0);
+    }
+
+    #[test]
+    fn test_calls_are_correlated_and_time_out() {
+        let embassy = safe::Embassy::new();
+        let ctx = embassy.context();
+        let mut reply = Envoy::empty();
+
+        // Nobody serves yet: the call times out and its request is withdrawn.
+        assert_eq!(
+            unsafe { praborrow_call_ctx(ctx, 1, b"lost".as_ptr(), 4, 10, &mut reply) },
+            PraborrowStatus::Timeout.code()
+        );
+        assert!(reply.data.is_null());
+        let registry = Arc::clone(unsafe { Context::from_ptr(ctx) }.unwrap().registry());
+        assert_eq!(registry.calls.stats().depth, 0);
+
+        let server = embassy.clone();
+        let served = std::thread::spawn(move || {
+            server.serve(|request: &Envelope| [b"re:", &request.payload[..]].concat());
+        });
+
+        for id in [7, 8] {
+            let status =
+                unsafe { praborrow_call_ctx(ctx, id, b"ping".as_ptr(), 4, u32::MAX, &mut reply) };
+            assert_eq!(status, SUCCESS);
+            assert_eq!(reply.id, id);
+            let payload = unsafe { std::slice::from_raw_parts(reply.data, reply.len) };
+            assert_eq!(payload, b"re:ping");
+            unsafe { free_envoy_bytes_ctx(ctx, reply.data) };
+        }
+
+        embassy.shutdown();
+        served.join().unwrap();
+        assert_eq!(
+            unsafe { praborrow_call_ctx(ctx, 9, std::ptr::null(), 0, u32::MAX, &mut reply) },
+            ERR_CLOSED
+        );
+        assert_eq!(unsafe { praborrow_context_free(ctx) }, 0);
+    }
+
+    #[test]
+    fn test_default_context_serves_calls() {
+        let _session = global_session();
+        let _ = sever_relations();
+        let mut reply = Envoy::empty();
+        assert_eq!(
+            unsafe { praborrow_call(1, std::ptr::null(), 0, 10, &mut reply) },
+            ERR_NOT_INITIALIZED
+        );
+
+        assert_eq!(establish_relations(), SUCCESS);
+        let served = std::thread::spawn(|| {
+            safe::Diplomat::serve(|request: &Envelope| {
+                if request.payload == b"panic" {
+                    panic!("handler failure");
+                }
+                [b"re:", &request.payload[..]].concat()
+            })
+        });
+
+        let status = unsafe { praborrow_call(7, b"ping".as_ptr(), 4, u32::MAX, &mut reply) };
+        assert_eq!(status, SUCCESS);
+        assert_eq!(reply.id, 7);
+        let payload = unsafe { std::slice::from_raw_parts(reply.data, reply.len) };
+        assert_eq!(payload, b"re:ping");
+        unsafe { free_envoy_bytes(reply.data) };
+
+        assert_eq!(
+            unsafe { praborrow_call(8, b"panic".as_ptr(), 5, u32::MAX, &mut reply) },
+            ERR_PANIC
+        );
+
+        assert_eq!(sever_relations(), 0);
+        served.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_priority_lanes_and_starvation() {
+        let config = DiplomacyConfig::new().incoming_lane_depth(Priority::High, 1);
+        let embassy = safe::Embassy::with_config(config).unwrap();
+        let ctx = embassy.context();
+        let msg = CString::new("x").unwrap();
+
+        assert_eq!(
+            unsafe { send_envoy_priority_ctx(ctx, 1, 0, msg.as_ptr()) },
+            SUCCESS
+        );
+        assert_eq!(unsafe { send_envoy_ctx(ctx, 2, msg.as_ptr()) }, SUCCESS);
+        assert_eq!(
+            unsafe { send_envoy_priority_ctx(ctx, 3, 2, msg.as_ptr()) },
+            SUCCESS
+        );
+        assert_eq!(
+            unsafe { send_envoy_priority_ctx(ctx, 4, 2, msg.as_ptr()) },
+            ERR_QUEUE_FULL
+        );
+        assert_eq!(
+            unsafe { send_envoy_priority_ctx(ctx, 5, 3, msg.as_ptr()) },
+            ERR_INVALID_PRIORITY
+        );
+        let order: Vec<u32> = std::iter::from_fn(|| embassy.receive_envelope())
+            .map(|envelope| envelope.id)
+            .collect();
+        assert_eq!(order, [3, 2, 1]);
+
+        // A steady stream of high-priority envoys still lets bulk through.
+        embassy.send_priority(100, Priority::Low, "bulk").unwrap();
+        for id in 0..mailbox::STARVATION_LIMIT as u32 {
+            embassy
+                .send_priority(id + 1, Priority::High, "ctl")
+                .unwrap();
+        }
+        let mut received = Vec::new();
+        for _ in 0..mailbox::STARVATION_LIMIT {
+            let mut envoy = Envoy::empty();
+            assert_eq!(
+                unsafe { receive_envoy_envelope_ctx(ctx, &mut envoy) },
+                SUCCESS
+            );
+            received.push(envoy.id);
+            unsafe { free_envoy_bytes_ctx(ctx, envoy.data) };
+        }
+        assert_eq!(received.last(), Some(&100));
+        assert_eq!(unsafe { praborrow_context_free(ctx) }, 0);
+    }
+
+    #[test]
+    fn test_expired_envoys_are_skipped_and_reported() {
+        let embassy = safe::Embassy::new();
+        let ctx = embassy.context();
+        let expired = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = Arc::clone(&expired);
+        embassy.set_expiry_handler(move |direction: Direction, envelope: &Envelope| {
+            sink.lock().unwrap().push((direction, envelope.id));
+        });
+
+        let msg = CString::new("stale").unwrap();
+        let mut options = praborrow_send_options_default();
+        options.ttl_ms = 1;
+        assert_eq!(
+            unsafe { send_envoy_with_options_ctx(ctx, 1, msg.as_ptr(), &options) },
+            SUCCESS
+        );
+        options.ttl_ms = 0;
+        assert_eq!(
+            unsafe { send_envoy_with_options_ctx(ctx, 2, msg.as_ptr(), &options) },
+            SUCCESS
+        );
+        options.priority = 7;
+        assert_eq!(
+            unsafe { send_envoy_with_options_ctx(ctx, 3, msg.as_ptr(), &options) },
+            ERR_INVALID_PRIORITY
+        );
+
+        let past = std::time::SystemTime::now() - Duration::from_secs(1);
+        embassy
+            .send_with(4, b"late", SendOptions::new().deadline(past))
+            .unwrap();
+        embassy.send(5, "fresh").unwrap();
+
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(embassy.receive_envelope().unwrap().id, 2);
+        assert!(embassy.receive_envelope().is_none());
+        let ptr = unsafe { receive_envoy_ctx(ctx) };
+        assert_eq!(unsafe { CStr::from_ptr(ptr) }.to_str().unwrap(), "fresh");
+        unsafe { free_envoy_ctx(ctx, ptr) };
+
+        assert_eq!(
+            *expired.lock().unwrap(),
+            [(Direction::Incoming, 1), (Direction::Outbox, 4)]
+        );
+        let report = embassy.overflow_report();
+        assert_eq!((report.incoming.expired, report.outbox.expired), (1, 1));
+
+        // Deadlines are read off the wall clock once and then kept monotonic.
+        let before = Instant::now();
+        let future = std::time::SystemTime::now() + Duration::from_secs(60);
+        let routing = SendOptions::new().deadline(future).resolve().unwrap();
+        let remaining = routing.expires_at.unwrap() - before;
+        assert!(remaining > Duration::from_secs(59) && remaining <= Duration::from_secs(61));
+        assert_eq!(unsafe { praborrow_context_free(ctx) }, 0);
+    }
+
+    #[test]
+    fn test_batches_report_partial_success() {
+        let config = DiplomacyConfig::new().incoming_depth(3);
+        let embassy = safe::Embassy::with_config(config).unwrap();
+        let ctx = embassy.context();
+
+        let payloads: [&[u8]; 4] = [b"a", b"bb", b"", b"dddd"];
+        let batch: Vec<Envoy> = payloads
+            .iter()
+            .zip(1..)
+            .map(|(payload, id)| Envoy {
+                id,
+                data: payload.as_ptr().cast_mut(),
+                len: payload.len(),
+                ..Envoy::empty()
+            })
+            .collect();
+        let mut sent = usize::MAX;
+        let status = unsafe { send_envoys_ctx(ctx, batch.as_ptr(), batch.len(), &mut sent) };
+        assert_eq!((status, sent), (ERR_QUEUE_FULL, 3));
+
+        let drained = embassy.drain(2);
+        assert_eq!(drained.iter().map(|e| e.id).collect::<Vec<_>>(), [1, 2]);
+        assert_eq!(embassy.drain(10).len(), 1);
+
+        assert_eq!(
+            embassy.send_batch([(7, "x"), (0, "bad")].map(|(id, p)| (id, p.as_bytes()))),
+            Ok(2)
+        );
+        let result = embassy.send_batch([(8, b"y".as_slice())]);
+        assert_eq!(result, Ok(1));
+
+        let mut out: Vec<Envoy> = (0..4).map(|_| Envoy::empty()).collect();
+        let received = unsafe { receive_envoys_ctx(ctx, out.as_mut_ptr(), out.len()) };
+        assert_eq!(received, 3);
+        let ids: Vec<u32> = out[..3].iter().map(|e| e.id).collect();
+        assert_eq!(ids, [7, 0, 8]);
+        unsafe { free_envoys_ctx(ctx, out.as_mut_ptr(), 3) };
+        assert!(out.iter().all(|e| e.data.is_null()));
+        assert_eq!(unsafe { praborrow_context_free(ctx) }, 0);
+    }
+
+    #[test]
+    fn test_receive_into_caller_buffer() {
+        let embassy = safe::Embassy::new();
+        let ctx = embassy.context();
+
+        embassy.send_bytes(1, b"long message").unwrap();
+        embassy.send_bytes(2, b"ok").unwrap();
+
+        // Sizing is a failure like any other, but leaves the envoy untouched.
+        let mut needed = 0;
+        let status = unsafe { receive_envoy_into_ctx(ctx, std::ptr::null_mut(), 0, &mut needed) };
+        assert_eq!((status, needed), (ERR_BUFFER_TOO_SMALL, 13));
+        assert_eq!(praborrow_last_error_code(), ERR_BUFFER_TOO_SMALL);
+        assert_eq!(embassy.stats().outbox.received, 0);
+
+        // Too small by one: the terminator must fit too. The envoy stays first.
+        let mut buf = [0x7f as c_char; 16];
+        let status = unsafe { receive_envoy_into_ctx(ctx, buf.as_mut_ptr(), 12, &mut needed) };
+        assert_eq!((status, needed), (ERR_BUFFER_TOO_SMALL, 13));
+        assert_eq!(buf[0], 0x7f);
+
+        let status = unsafe { receive_envoy_into_ctx(ctx, buf.as_mut_ptr(), 13, &mut needed) };
+        assert_eq!((status, needed), (SUCCESS, 13));
+        let text = unsafe { CStr::from_ptr(buf.as_ptr()) };
+        assert_eq!(text.to_bytes(), b"long message");
+
+        let status =
+            unsafe { receive_envoy_into_ctx(ctx, buf.as_mut_ptr(), buf.len(), &mut needed) };
+        assert_eq!((status, needed), (SUCCESS, 3));
+        let status =
+            unsafe { receive_envoy_into_ctx(ctx, buf.as_mut_ptr(), buf.len(), &mut needed) };
+        assert_eq!((status, needed), (EMPTY, 0));
+
+        let status = unsafe { receive_envoy_into_ctx(ctx, std::ptr::null_mut(), 1, &mut needed) };
+        assert_eq!(status, ERR_NULL_PTR);
+        assert_eq!(unsafe { praborrow_context_free(ctx) }, 0);
+    }
+
+    #[test]
+    fn test_handle_loans_detect_stale_use() {
+        let embassy = safe::Embassy::new();
+        let ctx = embassy.context();
+        embassy.send_bytes(3, b"first").unwrap();
+        embassy.send_bytes(4, b"second").unwrap();
+
+        let first = unsafe { receive_envoy_handle_ctx(ctx) };
+        assert_ne!(first, EnvoyHandle::NONE);
+        let (data, len) = unsafe { (envoy_data_ctx(ctx, first), envoy_len_ctx(ctx, first)) };
+        assert_eq!(unsafe { std::slice::from_raw_parts(data, len) }, b"first");
+        assert_eq!(unsafe { envoy_id_ctx(ctx, first) }, 3);
+        assert_eq!(unsafe { envoy_release_ctx(ctx, first) }, SUCCESS);
+
+        // The next loan reuses the slot under a new generation.
+        let second = unsafe { receive_envoy_handle_ctx(ctx) };
+        assert_ne!(second, first);
+        assert_eq!(unsafe { envoy_release_ctx(ctx, first) }, ERR_INVALID_HANDLE);
+        assert!(unsafe { envoy_data_ctx(ctx, first) }.is_null());
+        assert_eq!(unsafe { envoy_len_ctx(ctx, first) }, 0);
+        assert_eq!(unsafe { envoy_id_ctx(ctx, second) }, 4);
+
+        // First loans of two fresh contexts share a slot but not a handle.
+        let (here, there) = (safe::Embassy::new(), safe::Embassy::new());
+        let (here_ctx, there_ctx) = (here.context(), there.context());
+        here.send_bytes(8, b"here").unwrap();
+        there.send_bytes(9, b"there").unwrap();
+        let local = unsafe { receive_envoy_handle_ctx(here_ctx) };
+        let foreign = unsafe { receive_envoy_handle_ctx(there_ctx) };
+        assert!(unsafe { envoy_data_ctx(here_ctx, foreign) }.is_null());
+        assert_eq!(
+            unsafe { envoy_release_ctx(here_ctx, foreign) },
+            ERR_INVALID_HANDLE
+        );
+        assert_eq!(unsafe { envoy_release_ctx(here_ctx, local) }, SUCCESS);
+        assert_eq!(unsafe { envoy_release_ctx(there_ctx, foreign) }, SUCCESS);
+        for ctx in [here_ctx, there_ctx] {
+            assert_eq!(unsafe { praborrow_context_free(ctx) }, 0);
+        }
+
+        embassy.send_bytes(5, b"third").unwrap();
+        let third = unsafe { receive_envoy_handle_ctx(ctx) };
+        let data = unsafe { envoy_data_ctx(ctx, third) };
+
+        assert_eq!(unsafe { receive_envoy_handle_ctx(ctx) }, EnvoyHandle::NONE);
+        assert_eq!(embassy.shutdown().leaked_loans, 2);
+        assert_eq!(unsafe { envoy_release_ctx(ctx, second) }, SUCCESS);
+        assert_eq!(
+            unsafe { envoy_release_ctx(ctx, EnvoyHandle(7)) },
+            ERR_INVALID_HANDLE
+        );
+        assert_eq!(unsafe { praborrow_context_free(ctx) }, 1);
+
+        // The unreleased payload outlives the context, like a loaned pointer.
+        drop(embassy);
+        assert_eq!(unsafe { std::slice::from_raw_parts(data, 5) }, b"third");
+    }
+
+    #[test]
+    fn test_outstanding_loans_are_reported() {
+        let embassy = safe::Embassy::new();
+        let ctx = embassy.context();
+        for (id, payload) in [(1, "one"), (2, "four"), (3, "three")] {
+            embassy.send(id, payload).unwrap();
+        }
+
+        let first = unsafe { receive_envoy_ctx(ctx) };
+        let mut len = 0;
+        let second = unsafe { receive_envoy_bytes_ctx(ctx, &mut len) };
+        let third = unsafe { receive_envoy_handle_ctx(ctx) };
+        unsafe { free_envoy_ctx(ctx, first) };
+
+        let report = embassy.loan_report();
+        assert_eq!(report.summary.count, 2);
+        assert_eq!(report.summary.bytes, 9);
+        assert_eq!(
+            report.loans.iter().map(|loan| loan.id).collect::<Vec<_>>(),
+            [2, 3]
+        );
+        assert_eq!(report.summary.oldest_age_us, report.loans[0].age_us);
+
+        let mut summary = LoanSummary::default();
+        let mut out = [LoanInfo::default(); 1];
+        let written =
+            unsafe { praborrow_outstanding_loans_ctx(ctx, &mut summary, out.as_mut_ptr(), 1) };
+        assert_eq!((written, summary.count, out[0].id), (1, 2, 2));
+        let written = unsafe {
+            praborrow_outstanding_loans_ctx(ctx, std::ptr::null_mut(), out.as_mut_ptr(), 1)
+        };
+        assert_eq!(written, ERR_NULL_PTR);
+
+        unsafe { free_envoy_bytes_ctx(ctx, second) };
+        assert_eq!(unsafe { envoy_release_ctx(ctx, third) }, SUCCESS);
+        assert_eq!(embassy.loan_report(), LoanReport::default());
+        assert_eq!(unsafe { praborrow_context_free(ctx) }, 0);
+    }
+
+    #[test]
+    fn test_last_error_describes_failures() {
+        let embassy = safe::Embassy::new();
+        let ctx = embassy.context();
+        let message = || {
+            let needed = unsafe { praborrow_last_error_message(std::ptr::null_mut(), 0) };
+            let mut buf = vec![0 as c_char; needed as usize + 1];
+            unsafe { praborrow_last_error_message(buf.as_mut_ptr(), buf.len()) };
+            unsafe { CStr::from_ptr(buf.as_ptr()) }
+                .to_str()
+                .unwrap()
+                .to_owned()
+        };
+
+        let invalid = c"ok\xffno";
+        assert_eq!(
+            unsafe { send_envoy_ctx(ctx, 7, invalid.as_ptr()) },
+            ERR_INVALID_UTF8
+        );
+        assert_eq!(praborrow_last_error_code(), ERR_INVALID_UTF8);
+        assert_eq!(
+            message(),
+            "send_envoy_ctx (envoy 7): invalid UTF-8 at byte 2"
+        );
+
+        // Successful calls leave the record alone; the next failure replaces it.
+        assert_eq!(unsafe { send_envoy_ctx(ctx, 7, c"fine".as_ptr()) }, SUCCESS);
+        assert_eq!(praborrow_last_error_code(), ERR_INVALID_UTF8);
+        assert_eq!(
+            unsafe { send_envoy_priority_ctx(std::ptr::null(), 1, 0, c"x".as_ptr()) },
+            ERR_NULL_PTR
+        );
+        assert_eq!(message(), "send_envoy_priority_ctx: ctx is NULL");
+
+        // Truncated copies stay NUL-terminated and report the full length.
+        let mut small = [0x7f as c_char; 8];
+        let full = unsafe { praborrow_last_error_message(small.as_mut_ptr(), small.len()) };
+        assert_eq!(full as usize, "send_envoy_priority_ctx: ctx is NULL".len());
+        assert_eq!(
+            unsafe { CStr::from_ptr(small.as_ptr()) }.to_bytes(),
+            b"send_en"
+        );
+
+        // The record is per thread.
+        let other = std::thread::spawn(|| praborrow_last_error_code())
+            .join()
+            .unwrap();
+        assert_eq!(other, 0);
+        assert_eq!(unsafe { praborrow_context_free(ctx) }, 0);
+    }
+
+    #[test]
+    fn test_status_codes_round_trip() {
+        for code in -20..5 {
+            let Some(status) = PraborrowStatus::from_code(code) else {
+                let text = unsafe { CStr::from_ptr(praborrow_strerror(code)) };
+                assert_eq!(text.to_str().unwrap(), "Unknown status");
+                continue;
+            };
+            assert_eq!(c_int::from(status), code);
+            let text = unsafe { CStr::from_ptr(praborrow_strerror(code)) };
+            match safe::DiplomacyError::try_from(status) {
+                Ok(error) => {
+                    assert!(code < 0);
+                    assert_eq!(PraborrowStatus::from(error), status);
+                    assert_eq!(text.to_str().unwrap(), error.to_string());
+                }
+                Err(success) => assert_eq!((success, code >= 0), (status, true)),
+            }
+        }
+        // C callers hardcode the codes that predate the enum.
+        assert_eq!(
+            [
+                ERR_ALREADY_INIT,
+                ERR_INIT_FAILED,
+                ERR_NULL_PTR,
+                ERR_INVALID_UTF8,
+                ERR_INVALID_ID,
+                ERR_PANIC,
+                ERR_QUEUE_FULL,
+            ],
+            [-1, -2, -3, -4, -5, -6, -7]
+        );
+    }
+
+    #[test]
+    fn test_stats_track_traffic_and_misuse() {
+        let embassy = safe::Embassy::new();
+        let ctx = embassy.context();
+        let payload = CString::new("ping").unwrap();
+        for id in 1..=3 {
+            assert_eq!(unsafe { send_envoy_ctx(ctx, id, payload.as_ptr()) }, 0);
+        }
+        assert!(embassy.receive().is_some());
+        embassy.send(7, "pong").unwrap();
+        let reply = unsafe { receive_envoy_ctx(ctx) };
+        assert!(!reply.is_null());
+        unsafe { free_envoy_ctx(ctx, reply) };
+        unsafe { free_envoy_ctx(ctx, reply) };
+        let rejected_before = stats::rejects()[5];
+        assert_eq!(
+            unsafe { send_envoy_ctx(ctx, 0, payload.as_ptr()) },
+            ERR_INVALID_ID
+        );
+
+        let mut stats = DiplomacyStats::default();
+        assert_eq!(unsafe { praborrow_get_stats_ctx(ctx, &mut stats) }, 0);
+        assert_eq!(
+            stats.incoming,
+            QueueStats {
+                sent: 3,
+                received: 1,
+                depth: 2,
+                peak_depth: 3,
+                bytes: 8,
+            }
+        );
+        assert_eq!((stats.outbox.sent, stats.outbox.received), (1, 1));
+        assert_eq!((stats.outbox.depth, stats.outbox.peak_depth), (0, 1));
+        assert_eq!((stats.outstanding_loans, stats.double_frees), (0, 1));
+        assert!(stats.rejects[5] > rejected_before);
+        assert_eq!(stats.incoming, embassy.stats().incoming);
+        assert_eq!(
+            unsafe { praborrow_get_stats_ctx(ctx, std::ptr::null_mut()) },
+            ERR_NULL_PTR
+        );
+        assert_eq!(unsafe { praborrow_context_free(ctx) }, 0);
+    }
+
+    unsafe extern "C" fn collect_log(
+        level: LogLevel,
+        target: *const c_char,
+        message: *const c_char,
+        ctx: *mut c_void,
+    ) {
+        let logs = unsafe { &*(ctx as *const std::sync::Mutex<Vec<(LogLevel, String)>>) };
+        let target = unsafe { CStr::from_ptr(target) }.to_string_lossy();
+        let message = unsafe { CStr::from_ptr(message) }.to_string_lossy();
+        logs.lock()
+            .unwrap()
+            .push((level, format!("{target}: {message}")));
+    }
+
+    #[test]
+    fn test_log_callback_receives_events() {
+        let logs = std::sync::Mutex::new(Vec::<(LogLevel, String)>::new());
+        let logs_ptr = &logs as *const _ as *mut c_void;
+        assert_eq!(
+            unsafe { praborrow_set_log_callback(Some(collect_log), logs_ptr, LogLevel::WARN) },
+            0
+        );
+
+        let embassy = safe::Embassy::new();
+        let ctx = embassy.context();
+        let mut stranger = 0u8;
+        unsafe { free_envoy_bytes_ctx(ctx, &mut stranger) };
+        {
+            let logs = logs.lock().unwrap();
+            assert!(logs.iter().all(|(level, _)| *level >= LogLevel::WARN));
+            assert!(logs.iter().any(|(level, line)| {
+                *level == LogLevel::ERROR
+                    && line.starts_with("praborrow_diplomacy: Attempted to free invalid")
+                    && line.contains("event=ffi_violation")
+            }));
+        }
+
+        // The level can change at runtime without re-registering.
+        assert_eq!(praborrow_set_log_level(LogLevel::OFF), 0);
+        let seen = logs.lock().unwrap().len();
+        unsafe { free_envoy_bytes_ctx(ctx, &mut stranger) };
+        assert_eq!(logs.lock().unwrap().len(), seen);
+        assert_eq!(praborrow_set_log_level(LogLevel(9)), ERR_INVALID_CONFIG);
+
+        assert_eq!(
+            unsafe { praborrow_set_log_callback(None, std::ptr::null_mut(), LogLevel::OFF) },
+            0
+        );
+        assert_eq!(unsafe { praborrow_context_free(ctx) }, 0);
+    }
+
+    #[test]
+    fn test_payload_log_modes() {
+        let payload = b"token=hunter2";
+        let shown = |mode, max_bytes| {
+            logging::payload(PayloadLogConfig { mode, max_bytes }, payload)
+                .map(|value| format!("{value:?}"))
+        };
+
+        assert_eq!(shown(PayloadLogMode::OMIT, 64), None);
+        assert_eq!(
+            shown(PayloadLogMode::FULL, 0).as_deref(),
+            Some("\"token=hunter2\"")
+        );
+        assert_eq!(
+            shown(PayloadLogMode::TRUNCATED, 6).as_deref(),
+            Some("\"token=\"... (7 more bytes)")
+        );
+        assert_eq!(
+            shown(PayloadLogMode::TRUNCATED, 64).as_deref(),
+            Some("\"token=hunter2\"")
+        );
+        let hashed = shown(PayloadLogMode::HASHED, 0).unwrap();
+        assert!(hashed.starts_with("fnv1a:") && !hashed.contains("hunter2"));
+        assert_eq!(shown(PayloadLogMode::HASHED, 0).unwrap(), hashed);
+
+        // Unknown modes are rejected like unknown overflow policies.
+        let config = DiplomacyConfig::new().payload_log_mode(PayloadLogMode(99));
+        assert!(matches!(
+            safe::Embassy::with_config(config),
+            Err(safe::DiplomacyError::InvalidConfig)
+        ));
+        let config = DiplomacyConfig::new()
+            .payload_log_mode(PayloadLogMode::TRUNCATED)
+            .payload_log_bytes(16);
+        let embassy = safe::Embassy::with_config(config).unwrap();
+        embassy.send(1, "secret").unwrap();
+    }
+
+    /// Declarations of a C header, one per line, without comments or layout.
+    fn abi_declarations(header: &str) -> String {
+        let mut code = String::new();
+        let mut rest = header;
+        while let Some(start) = rest.find("/*") {
+            code.push_str(&rest[..start]);
+            let end = rest[start..].find("*/").expect("unterminated comment");
+            rest = &rest[start + end + 2..];
+        }
+        code.push_str(rest);
+
+        let mut declarations = String::new();
+        let mut pending: Vec<&str> = Vec::new();
+        let mut depth = 0;
+        for line in code.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            pending.extend(line.split_whitespace());
+            depth += line.matches('(').count() as isize - line.matches(')').count() as isize;
+            // Prototypes span lines; enum variants and fields get one each.
+            if line.starts_with('#') || (depth == 0 && line.ends_with([';', '{', ','])) {
+                declarations.push_str(&pending.join(" "));
+                declarations.push('\n');
+                pending.clear();
+            }
+        }
+        declarations
+    }
+
+    #[test]
+    fn test_c_header_matches_abi() {
+        let header = std::fs::read_to_string(concat!(env!("OUT_DIR"), "/praborrow.h")).unwrap();
+
+        // Any change to the declarations needs a new ABI version.
+        let version = ABI_VERSION.to_string();
+        let snapshot = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("abi/praborrow.abi");
+        let recorded = std::fs::read_to_string(&snapshot).unwrap_or_default();
+        let (recorded_version, recorded_declarations) = recorded
+            .strip_prefix("abi ")
+            .and_then(|rest| rest.split_once('\n'))
+            .unwrap_or_default();
+        let declarations = abi_declarations(&header);
+        let changed = recorded_declarations != declarations;
+        assert!(
+            !changed || recorded_version != version,
+            "exported C declarations changed without bumping ABI_VERSION (still {version})"
+        );
+        if std::env::var_os("PRABORROW_BLESS_ABI").is_some() {
+            std::fs::create_dir_all(snapshot.parent().unwrap()).unwrap();
+            std::fs::write(&snapshot, format!("abi {version}\n{declarations}")).unwrap();
+        } else {
+            assert!(
+                !changed && recorded_version == version,
+                "abi/praborrow.abi is out of date; run the tests with PRABORROW_BLESS_ABI=1"
+            );
+        }
+    }
+
+    /// Needs a C compiler, so it only runs with `PRABORROW_CHECK_C_HEADER` set;
+    /// `CC` picks the compiler.
+    #[test]
+    fn test_c_header_compiles() {
+        if std::env::var_os("PRABORROW_CHECK_C_HEADER").is_none() {
+            return;
+        }
+        let out_dir = env!("OUT_DIR");
+
+        // The header must compile, with the layouts Rust uses.
+        let layouts = [
+            ("praborrow_envoy", size_of::<Envoy>(), align_of::<Envoy>()),
+            (
+                "praborrow_config",
+                size_of::<DiplomacyConfig>(),
+                align_of::<DiplomacyConfig>(),
+            ),
+            (
+                "praborrow_queue_config",
+                size_of::<QueueConfig>(),
+                align_of::<QueueConfig>(),
+            ),
+            (
+                "praborrow_loan_config",
+                size_of::<LoanConfig>(),
+                align_of::<LoanConfig>(),
+            ),
+            (
+                "praborrow_payload_log_config",
+                size_of::<PayloadLogConfig>(),
+                align_of::<PayloadLogConfig>(),
+            ),
+            (
+                "praborrow_send_options",
+                size_of::<SendOptions>(),
+                align_of::<SendOptions>(),
+            ),
+            (
+                "praborrow_overflow_report",
+                size_of::<OverflowReport>(),
+                align_of::<OverflowReport>(),
+            ),
+            (
+                "praborrow_loan_info",
+                size_of::<LoanInfo>(),
+                align_of::<LoanInfo>(),
+            ),
+            (
+                "praborrow_loan_summary",
+                size_of::<LoanSummary>(),
+                align_of::<LoanSummary>(),
+            ),
+            (
+                "praborrow_envoy_handle",
+                size_of::<EnvoyHandle>(),
+                align_of::<EnvoyHandle>(),
+            ),
+            (
+                "praborrow_status",
+                size_of::<PraborrowStatus>(),
+                align_of::<PraborrowStatus>(),
+            ),
+            (
+                "praborrow_log_level",
+                size_of::<LogLevel>(),
+                align_of::<LogLevel>(),
+            ),
+            (
+                "praborrow_stats",
+                size_of::<DiplomacyStats>(),
+                align_of::<DiplomacyStats>(),
+            ),
+        ];
+        let mut program = String::from("#include \"praborrow.h\"\n\n");
+        for (name, size, align) in layouts {
+            program.push_str(&format!(
+                "_Static_assert(sizeof({name}) == {size} && _Alignof({name}) == {align}, \"{name}\");\n"
+            ));
+        }
+        program.push_str(concat!(
+            "\nint main(void) {\n",
+            "    praborrow_config config = praborrow_config_default();\n",
+            "    if (establish_relations_with_config(&config) != PRABORROW_STATUS_SUCCESS) {\n",
+            "        return praborrow_last_error_code();\n",
+            "    }\n",
+            "    return praborrow_version() != NULL && sever_relations() == 0 ? 0 : 1;\n",
+            "}\n",
+        ));
+        let source = std::env::temp_dir().join(format!("praborrow_abi_{}.c", std::process::id()));
+        std::fs::write(&source, program).unwrap();
+        let compiler = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+        let output = std::process::Command::new(&compiler)
+            .args([
+                "-std=c11",
+                "-Wall",
+                "-Wextra",
+                "-Werror",
+                "-fsyntax-only",
+                "-I",
+                out_dir,
+            ])
+            .arg(&source)
+            .output();
+        std::fs::remove_file(&source).unwrap();
+        let output =
+            output.unwrap_or_else(|e| panic!("cannot run C compiler {compiler} (set CC): {e}"));
+        assert!(
+            output.status.success(),
+            "C program does not compile against praborrow.h:\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
     }
 }
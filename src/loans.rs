@@ -0,0 +1,215 @@
+//! Bookkeeping of envoys on loan to the foreign jurisdiction.
+
+use crate::Envelope;
+use crate::config::LoanConfig;
+use crate::handles::{EnvoyHandle, HandleTable};
+use dashmap::DashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Instant;
+
+/// How a loaned pointer was allocated, so it is released the same way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LoanKind {
+    /// A `CString` handed out by `receive_envoy`.
+    CString,
+    /// A boxed byte slice handed out by `receive_envoy_bytes`.
+    ///
+    /// `alloc_len` is at least 1 so that every loan has a distinct address.
+    Bytes { alloc_len: usize },
+}
+
+/// A pointer on loan, with what is needed to release and report it.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Loan {
+    pub(crate) kind: LoanKind,
+    id: u32,
+    len: usize,
+    lent_at: Instant,
+}
+
+impl Loan {
+    /// A loan of a `len`-byte payload of envoy `id`, starting now.
+    pub(crate) fn new(kind: LoanKind, id: u32, len: usize) -> Self {
+        Self {
+            kind,
+            id,
+            len,
+            lent_at: Instant::now(),
+        }
+    }
+}
+
+/// One envoy the foreign side has not returned yet.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LoanInfo {
+    /// Id of the loaned envoy.
+    pub id: u32,
+    /// Payload length in bytes.
+    pub len: usize,
+    /// Microseconds since the envoy was received.
+    pub age_us: u64,
+}
+
+impl LoanInfo {
+    /// Describes a loan of envoy `id` made at `lent_at`, as seen at `now`.
+    pub(crate) fn new(id: u32, len: usize, lent_at: Instant, now: Instant) -> Self {
+        let age = now.saturating_duration_since(lent_at);
+        Self {
+            id,
+            len,
+            age_us: u64::try_from(age.as_micros()).unwrap_or(u64::MAX),
+        }
+    }
+}
+
+/// Totals over all unreturned envoys of a context.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LoanSummary {
+    /// Number of unreturned envoys.
+    pub count: usize,
+    /// Total payload bytes of unreturned envoys.
+    pub bytes: usize,
+    /// Age of the oldest unreturned envoy in microseconds, 0 if there is none.
+    pub oldest_age_us: u64,
+}
+
+/// Envoys received by the foreign side and not yet freed or released.
+///
+/// Covers pointers from `receive_envoy`, `receive_envoy_bytes` and friends
+/// as well as handles from `receive_envoy_handle`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct LoanReport {
+    pub summary: LoanSummary,
+    /// Every unreturned envoy, oldest first.
+    pub loans: Vec<LoanInfo>,
+}
+
+/// Every loan of one context, by pointer or by handle.
+pub(crate) struct LoanLedger {
+    /// Pointers given to C, keyed by address, to prevent double-free.
+    pointers: DashMap<usize, Loan>,
+    handles: HandleTable,
+    /// Pointers and handles on loan, kept separately to detect threshold crossings.
+    outstanding: AtomicUsize,
+    /// Returns of pointers and handles that were not on loan.
+    double_frees: AtomicU64,
+    config: LoanConfig,
+}
+
+impl LoanLedger {
+    pub(crate) fn new(config: LoanConfig) -> Self {
+        Self {
+            pointers: DashMap::new(),
+            handles: HandleTable::default(),
+            outstanding: AtomicUsize::new(0),
+            double_frees: AtomicU64::new(0),
+            config,
+        }
+    }
+
+    /// Records a pointer handed to the foreign side.
+    pub(crate) fn lend_pointer(&self, ptr: usize, loan: Loan) {
+        self.pointers.insert(ptr, loan);
+        self.lent();
+    }
+
+    /// Ends the loan of a pointer, or returns `None` if it is not on loan.
+    pub(crate) fn return_pointer(&self, ptr: usize) -> Option<Loan> {
+        let Some((_, loan)) = self.pointers.remove(&ptr) else {
+            self.double_frees.fetch_add(1, Ordering::Relaxed);
+            return None;
+        };
+        self.returned();
+        Some(loan)
+    }
+
+    /// Keeps `envelope` until the returned handle is released.
+    pub(crate) fn lend_handle(&self, envelope: Envelope) -> EnvoyHandle {
+        let handle = self.handles.lend(envelope);
+        self.lent();
+        handle
+    }
+
+    /// Runs `f` on the envelope behind `handle`, if it is on loan.
+    pub(crate) fn with_handle<R>(
+        &self,
+        handle: EnvoyHandle,
+        f: impl FnOnce(&Envelope) -> R,
+    ) -> Option<R> {
+        self.handles.with(handle, f)
+    }
+
+    /// Ends the loan of a handle, or returns `None` if it is not on loan.
+    pub(crate) fn return_handle(&self, handle: EnvoyHandle) -> Option<Envelope> {
+        let Some(envelope) = self.handles.release(handle) else {
+            self.double_frees.fetch_add(1, Ordering::Relaxed);
+            return None;
+        };
+        self.returned();
+        Some(envelope)
+    }
+
+    /// Number of pointers and handles on loan.
+    pub(crate) fn len(&self) -> usize {
+        self.pointers.len() + self.handles.len()
+    }
+
+    /// Number of returns of pointers and handles that were not on loan.
+    pub(crate) fn double_frees(&self) -> u64 {
+        self.double_frees.load(Ordering::Relaxed)
+    }
+
+    /// Lists every loan, oldest first.
+    pub(crate) fn report(&self) -> LoanReport {
+        // One reference instant, so that ages order loans like their lending.
+        let now = Instant::now();
+        let mut loans: Vec<LoanInfo> = self
+            .pointers
+            .iter()
+            .map(|entry| LoanInfo::new(entry.id, entry.len, entry.lent_at, now))
+            .collect();
+        self.handles.report_into(&mut loans, now);
+        loans.sort_by_key(|loan| std::cmp::Reverse(loan.age_us));
+
+        let summary = LoanSummary {
+            count: loans.len(),
+            bytes: loans.iter().map(|loan| loan.len).sum(),
+            oldest_age_us: loans.first().map_or(0, |loan| loan.age_us),
+        };
+        LoanReport { summary, loans }
+    }
+
+    /// Logs the loans still outstanding at shutdown, if configured to.
+    pub(crate) fn warn_if_leaked(&self) {
+        if !self.config.warn_on_sever || self.len() == 0 {
+            return;
+        }
+        let report = self.report();
+        let ids: Vec<u32> = report.loans.iter().map(|loan| loan.id).collect();
+        tracing::warn!(
+            event = "loans_leaked",
+            count = report.summary.count,
+            bytes = report.summary.bytes,
+            oldest_age_us = report.summary.oldest_age_us,
+            envoy_ids = ?ids,
+            "Relations severed with envoys still on loan to the foreign jurisdiction"
+        );
+    }
+
+    fn lent(&self) {
+        let outstanding = self.outstanding.fetch_add(1, Ordering::Relaxed) + 1;
+        if outstanding == self.config.warn_threshold {
+            tracing::warn!(
+                event = "loans_outstanding",
+                outstanding,
+                "Foreign jurisdiction holds many envoys without freeing them"
+            );
+        }
+    }
+
+    fn returned(&self) {
+        self.outstanding.fetch_sub(1, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,291 @@
+//! Forwarding of `tracing` events to a foreign logging callback.
+//!
+//! A C host has no `tracing` subscriber, so `praborrow_set_log_callback`
+//! installs one whose only layer is [`layer`]. A Rust host with its own
+//! subscriber can add [`layer`] to it instead.
+//!
+//! Payloads only appear in debug events as their context's
+//! [`PayloadLogConfig`] allows.
+
+use crate::config::{PayloadLogConfig, PayloadLogMode};
+use std::cell::Cell;
+use std::ffi::{CString, c_char, c_void};
+use std::fmt::{self, Write};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Condvar, Mutex, Once, PoisonError};
+use tracing::field::{DisplayValue, Field, Visit};
+use tracing::level_filters::LevelFilter;
+use tracing::{Event, Level, Metadata, Subscriber};
+use tracing_subscriber::layer::{Context, Filter, Layer, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Severity of a log event, from [`LogLevel::TRACE`] up to [`LogLevel::ERROR`].
+///
+/// Levels above [`LogLevel::OFF`] are rejected with `ERR_INVALID_CONFIG`.
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LogLevel(pub u32);
+
+impl LogLevel {
+    /// Step-by-step detail.
+    pub const TRACE: Self = Self(0);
+    /// Diagnostics such as every envoy received.
+    pub const DEBUG: Self = Self(1);
+    /// Lifecycle events such as relations being established.
+    pub const INFO: Self = Self(2);
+    /// Recoverable trouble such as a dropped reply.
+    pub const WARN: Self = Self(3);
+    /// Failed calls and misuse such as freeing an envoy twice.
+    pub const ERROR: Self = Self(4);
+    /// As a minimum level: forward nothing.
+    pub const OFF: Self = Self(5);
+
+    pub(crate) fn is_known(self) -> bool {
+        self <= Self::OFF
+    }
+
+    fn of(level: &Level) -> Self {
+        match *level {
+            Level::TRACE => Self::TRACE,
+            Level::DEBUG => Self::DEBUG,
+            Level::INFO => Self::INFO,
+            Level::WARN => Self::WARN,
+            Level::ERROR => Self::ERROR,
+        }
+    }
+
+    /// The `tracing` filter that lets through this level and more severe ones.
+    fn filter(self) -> LevelFilter {
+        match self {
+            Self::TRACE => LevelFilter::TRACE,
+            Self::DEBUG => LevelFilter::DEBUG,
+            Self::INFO => LevelFilter::INFO,
+            Self::WARN => LevelFilter::WARN,
+            Self::ERROR => LevelFilter::ERROR,
+            _ => LevelFilter::OFF,
+        }
+    }
+}
+
+/// Foreign function that receives log events.
+///
+/// Called as `callback(level, target, message, ctx)`. `target` names the
+/// module that logged, such as `praborrow_diplomacy`; `message` is the text of
+/// the event followed by its fields as `key=value`. Both are only valid for the
+/// duration of the call. `None` (NULL in C) unregisters.
+pub type LogCallbackFn = Option<
+    unsafe extern "C" fn(
+        level: LogLevel,
+        target: *const c_char,
+        message: *const c_char,
+        ctx: *mut c_void,
+    ),
+>;
+
+/// A registered log callback together with its foreign context.
+#[derive(Clone, Copy)]
+struct LogCallback {
+    func: unsafe extern "C" fn(LogLevel, *const c_char, *const c_char, *mut c_void),
+    ctx: *mut c_void,
+}
+
+// SAFETY: the foreign side promises, by registering, that `ctx` may be used
+// from whichever thread logs.
+unsafe impl Send for LogCallback {}
+
+struct Sink {
+    callback: Option<LogCallback>,
+    in_flight: usize,
+}
+
+/// The log callback, shared by every context, and its running invocations.
+static SINK: Mutex<Sink> = Mutex::new(Sink {
+    callback: None,
+    in_flight: 0,
+});
+static IDLE: Condvar = Condvar::new();
+static MIN_LEVEL: AtomicU32 = AtomicU32::new(LogLevel::OFF.0);
+static INSTALL: Once = Once::new();
+
+thread_local! {
+    /// Set while this thread runs the log callback, whose own events are dropped.
+    static FORWARDING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Replaces the log callback and its minimum level, and returns once no old
+/// invocation is running (unless called from inside the callback).
+///
+/// The first call installs a global subscriber that forwards to the callback.
+/// If the process already has one, events are only forwarded if it includes
+/// [`layer`].
+pub(crate) fn set_callback(callback: LogCallbackFn, ctx: *mut c_void, min_level: LogLevel) {
+    INSTALL.call_once(|| {
+        let subscriber = tracing_subscriber::registry().with(layer());
+        if tracing::subscriber::set_global_default(subscriber).is_err() {
+            tracing::warn!(
+                "A tracing subscriber is already installed; add \
+                 praborrow_diplomacy::logging::layer() to it to forward events"
+            );
+        }
+    });
+
+    let mut sink = SINK.lock().unwrap_or_else(PoisonError::into_inner);
+    sink.callback = callback.map(|func| LogCallback { func, ctx });
+    set_min_level(min_level);
+    if FORWARDING.with(Cell::get) {
+        return;
+    }
+    while sink.in_flight > 0 {
+        sink = IDLE.wait(sink).unwrap_or_else(PoisonError::into_inner);
+    }
+}
+
+/// Changes the least severe level that is forwarded.
+pub(crate) fn set_min_level(min_level: LogLevel) {
+    MIN_LEVEL.store(min_level.0, Ordering::Relaxed);
+    // Callsites cache whether they are enabled; make them ask again.
+    tracing::callsite::rebuild_interest_cache();
+}
+
+fn min_level() -> LogLevel {
+    LogLevel(MIN_LEVEL.load(Ordering::Relaxed))
+}
+
+/// Layer that forwards events at or above the minimum level to the callback
+/// set by `praborrow_set_log_callback`.
+pub fn layer<S>() -> impl Layer<S>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    ForeignLog.with_filter(MinLevel)
+}
+
+/// Per-layer filter following the runtime minimum level.
+struct MinLevel;
+
+impl<S> Filter<S> for MinLevel {
+    fn enabled(&self, metadata: &Metadata<'_>, _: &Context<'_, S>) -> bool {
+        LogLevel::of(metadata.level()) >= min_level()
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        Some(min_level().filter())
+    }
+}
+
+struct ForeignLog;
+
+impl<S: Subscriber> Layer<S> for ForeignLog {
+    fn on_event(&self, event: &Event<'_>, _: Context<'_, S>) {
+        if FORWARDING.with(Cell::get) {
+            return;
+        }
+        let callback = {
+            let mut sink = SINK.lock().unwrap_or_else(PoisonError::into_inner);
+            let Some(callback) = sink.callback else {
+                return;
+            };
+            sink.in_flight += 1;
+            callback
+        };
+
+        let metadata = event.metadata();
+        let mut message = Message::default();
+        event.record(&mut message);
+        let target = c_string(metadata.target().to_string());
+        let message = c_string((message.text + &message.fields).trim_start().to_string());
+
+        FORWARDING.with(|forwarding| forwarding.set(true));
+        // SAFETY: registration promises that `func` accepts these arguments;
+        // the strings outlive the call.
+        unsafe {
+            (callback.func)(
+                LogLevel::of(metadata.level()),
+                target.as_ptr(),
+                message.as_ptr(),
+                callback.ctx,
+            )
+        };
+        FORWARDING.with(|forwarding| forwarding.set(false));
+
+        let mut sink = SINK.lock().unwrap_or_else(PoisonError::into_inner);
+        sink.in_flight -= 1;
+        if sink.in_flight == 0 {
+            IDLE.notify_all();
+        }
+    }
+}
+
+/// Text and `key=value` fields of an event.
+#[derive(Default)]
+struct Message {
+    text: String,
+    fields: String,
+}
+
+impl Message {
+    fn push(&mut self, field: &Field, value: fmt::Arguments<'_>) {
+        if field.name() == "message" {
+            let _ = self.text.write_fmt(value);
+        } else {
+            let _ = write!(self.fields, " {}={value}", field.name());
+        }
+    }
+}
+
+impl Visit for Message {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.push(field, format_args!("{value}"));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.push(field, format_args!("{value:?}"));
+    }
+}
+
+/// `text` as a C string, with NUL bytes escaped.
+fn c_string(text: String) -> CString {
+    CString::new(text).unwrap_or_else(|e| {
+        let text = String::from_utf8_lossy(&e.into_vec()).replace('\0', "\\0");
+        CString::new(text).expect("NUL bytes were escaped")
+    })
+}
+
+/// `payload` as a log field shown as `config` says, or `None` to leave the
+/// field out. The payload is only rendered if the event is logged.
+pub(crate) fn payload(
+    config: PayloadLogConfig,
+    payload: &[u8],
+) -> Option<DisplayValue<LoggedPayload<'_>>> {
+    (config.mode != PayloadLogMode::OMIT)
+        .then(|| tracing::field::display(LoggedPayload { config, payload }))
+}
+
+/// A payload as it appears in debug events.
+pub(crate) struct LoggedPayload<'a> {
+    config: PayloadLogConfig,
+    payload: &'a [u8],
+}
+
+impl fmt::Display for LoggedPayload<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let shown = match self.config.mode {
+            PayloadLogMode::HASHED => return write!(f, "fnv1a:{:016x}", fnv1a(self.payload)),
+            PayloadLogMode::TRUNCATED => self.config.max_bytes.min(self.payload.len()),
+            _ => self.payload.len(),
+        };
+        write!(f, "{:?}", String::from_utf8_lossy(&self.payload[..shown]))?;
+        match self.payload.len() - shown {
+            0 => Ok(()),
+            rest => write!(f, "... ({rest} more bytes)"),
+        }
+    }
+}
+
+/// 64-bit FNV-1a, stable across builds and platforms so that hashes logged
+/// by different processes can be compared.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
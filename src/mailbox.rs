@@ -0,0 +1,679 @@
+//! A bounded envoy queue for one direction of a diplomatic context.
+
+use crate::config::OverflowPolicy;
+use crate::envelope::{PRIORITY_LANES, Priority};
+use crate::expiry::ExpiryHooks;
+use crate::handler::Direction;
+#[cfg(unix)]
+use crate::readiness::Readiness;
+use crate::safe::DiplomacyError;
+use crate::stats::{OverflowCounters, OverflowCounts, QueueStats};
+use crate::{Envelope, QueueConfig};
+use crossbeam_queue::SegQueue;
+use std::collections::VecDeque;
+#[cfg(unix)]
+use std::os::fd::RawFd;
+#[cfg(unix)]
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, PoisonError};
+#[cfg(feature = "async")]
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+
+/// Consecutive receives from a higher lane while a lower lane waits, after
+/// which the lowest waiting lane is served once so it cannot starve.
+pub(crate) const STARVATION_LIMIT: usize = 16;
+
+/// How an envelope admitted by [`Mailbox::admit`] must be handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Admission {
+    /// Room was reserved; push the envelope.
+    Queue,
+    /// The mailbox is full; hand the envelope to the spill handler instead.
+    Spill,
+}
+
+/// One FIFO per priority, with the number of envelopes queued or reserved in it.
+///
+/// Envelopes handed back by [`Mailbox::restore`] wait in `front`, ahead of
+/// `queue`; `front_len` lets receivers skip its lock while it is empty.
+#[derive(Default)]
+struct Lane {
+    queue: SegQueue<Envelope>,
+    front: Mutex<VecDeque<Envelope>>,
+    front_len: AtomicUsize,
+    count: AtomicUsize,
+}
+
+impl Lane {
+    fn is_empty(&self) -> bool {
+        self.front_len.load(Ordering::Acquire) == 0 && self.queue.is_empty()
+    }
+
+    fn pop(&self) -> Option<Envelope> {
+        if self.front_len.load(Ordering::Acquire) > 0 {
+            let mut front = self.front.lock().unwrap_or_else(PoisonError::into_inner);
+            if let Some(envelope) = front.pop_front() {
+                self.front_len.store(front.len(), Ordering::Release);
+                return Some(envelope);
+            }
+        }
+        self.queue.pop()
+    }
+
+    /// Looks at the oldest envelope, moving it into `front` so it stays first.
+    ///
+    /// An expired envelope is removed instead and returned as `Err`. The flag
+    /// says whether an envelope left `queue`, which receivers must be told.
+    fn peek(&self) -> (Option<Result<(u32, usize), Envelope>>, bool) {
+        let mut front = self.front.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut moved = false;
+        if front.is_empty() {
+            let Some(envelope) = self.queue.pop() else {
+                return (None, false);
+            };
+            front.push_back(envelope);
+            moved = true;
+        }
+        let head = match front.front() {
+            Some(envelope) if !envelope.is_expired() => Ok((envelope.id, envelope.payload.len())),
+            _ => Err(front.pop_front().expect("front holds an envelope")),
+        };
+        self.front_len.store(front.len(), Ordering::Release);
+        (Some(head), moved)
+    }
+
+    fn push_front(&self, envelope: Envelope) {
+        let mut front = self.front.lock().unwrap_or_else(PoisonError::into_inner);
+        front.push_front(envelope);
+        self.front_len.store(front.len(), Ordering::Release);
+    }
+}
+
+/// Lock-free priority queue of envelopes with depth and byte bounds and
+/// blocking receive.
+///
+/// Pushes and non-blocking pops never take `lock`; it only exists so that
+/// blocked receivers cannot miss the wake-up of a concurrent push.
+pub(crate) struct Mailbox {
+    lanes: [Lane; PRIORITY_LANES],
+    /// Receives served from a higher lane while a lower one waited.
+    streak: AtomicUsize,
+    config: QueueConfig,
+    count: AtomicUsize,
+    /// Total payload bytes queued or reserved.
+    bytes: AtomicUsize,
+    /// Highest `count` so far.
+    peak: AtomicUsize,
+    /// Envelopes pushed and handed to receivers, for [`QueueStats`].
+    sent: AtomicU64,
+    received: AtomicU64,
+    closed: AtomicBool,
+    lock: Mutex<()>,
+    ready: Condvar,
+    /// Senders blocked by [`OverflowPolicy::BLOCK`] wait on `room`. A separate
+    /// lock, because `pop_wait` pops while holding `lock`.
+    room_lock: Mutex<()>,
+    room: Condvar,
+    overflow: OverflowCounters,
+    /// Which queue this is, and who to tell when it discards an expired envelope.
+    direction: Direction,
+    expiry: Arc<ExpiryHooks>,
+    /// Pollable descriptor, created the first time it is requested.
+    #[cfg(unix)]
+    readiness: OnceLock<Option<Readiness>>,
+    /// Async receivers waiting for an envelope to arrive.
+    #[cfg(feature = "async")]
+    arrivals: WakerSet,
+    /// Async senders waiting for room in the queue.
+    #[cfg(feature = "async")]
+    departures: WakerSet,
+}
+
+impl Mailbox {
+    pub(crate) fn new(config: QueueConfig, direction: Direction, expiry: Arc<ExpiryHooks>) -> Self {
+        Self {
+            lanes: Default::default(),
+            streak: AtomicUsize::new(0),
+            config,
+            count: AtomicUsize::new(0),
+            bytes: AtomicUsize::new(0),
+            peak: AtomicUsize::new(0),
+            sent: AtomicU64::new(0),
+            received: AtomicU64::new(0),
+            closed: AtomicBool::new(false),
+            lock: Mutex::new(()),
+            ready: Condvar::new(),
+            room_lock: Mutex::new(()),
+            room: Condvar::new(),
+            overflow: OverflowCounters::default(),
+            direction,
+            expiry,
+            #[cfg(unix)]
+            readiness: OnceLock::new(),
+            #[cfg(feature = "async")]
+            arrivals: WakerSet::default(),
+            #[cfg(feature = "async")]
+            departures: WakerSet::default(),
+        }
+    }
+
+    /// Reserves room for one envelope of `len` payload bytes in `priority`'s
+    /// lane, failing if the depth, lane depth or byte budget would be exceeded.
+    ///
+    /// Reserving with `fetch_add` before pushing keeps concurrent senders
+    /// from overshooting the bounds.
+    pub(crate) fn reserve(&self, len: usize, priority: Priority) -> bool {
+        let depth = self.count.fetch_add(1, Ordering::Relaxed);
+        if depth >= self.config.max_depth {
+            self.count.fetch_sub(1, Ordering::Relaxed);
+            return false;
+        }
+        let lane = &self.lanes[priority.lane()];
+        if lane.count.fetch_add(1, Ordering::Relaxed) >= self.config.lane_depth[priority.lane()] {
+            lane.count.fetch_sub(1, Ordering::Relaxed);
+            self.count.fetch_sub(1, Ordering::Relaxed);
+            return false;
+        }
+        let queued = self.bytes.fetch_add(len, Ordering::Relaxed);
+        if queued.saturating_add(len) > self.config.max_bytes {
+            self.bytes.fetch_sub(len, Ordering::Relaxed);
+            lane.count.fetch_sub(1, Ordering::Relaxed);
+            self.count.fetch_sub(1, Ordering::Relaxed);
+            return false;
+        }
+        self.peak.fetch_max(depth + 1, Ordering::Relaxed);
+        true
+    }
+
+    /// Reserves room for one envelope of `len` payload bytes, applying the
+    /// configured overflow policy if the mailbox is full.
+    ///
+    /// `can_spill` says whether a spill handler is installed; without one,
+    /// [`OverflowPolicy::SPILL`] rejects. An envelope larger than the whole
+    /// byte budget is always rejected, as no amount of room would fit it.
+    pub(crate) fn admit(
+        &self,
+        len: usize,
+        priority: Priority,
+        can_spill: bool,
+    ) -> Result<Admission, DiplomacyError> {
+        if self.reserve(len, priority) {
+            return Ok(Admission::Queue);
+        }
+
+        let fits = self.fits(len);
+        match self.config.overflow {
+            OverflowPolicy::DROP_OLDEST if fits => self.admit_dropping_oldest(len, priority),
+            OverflowPolicy::BLOCK if fits => self.admit_blocking(len, priority),
+            OverflowPolicy::SPILL if can_spill => {
+                OverflowCounters::bump(&self.overflow.spilled);
+                Ok(Admission::Spill)
+            }
+            _ => {
+                OverflowCounters::bump(&self.overflow.rejected);
+                Err(DiplomacyError::QueueFull)
+            }
+        }
+    }
+
+    fn admit_dropping_oldest(
+        &self,
+        len: usize,
+        priority: Priority,
+    ) -> Result<Admission, DiplomacyError> {
+        loop {
+            if self.reserve(len, priority) {
+                return Ok(Admission::Queue);
+            }
+            if self.evict(priority).is_some() {
+                OverflowCounters::bump(&self.overflow.dropped_oldest);
+                continue;
+            }
+            // Nothing left to drop: the room is held by envoys of a higher
+            // priority, or by concurrent senders that have reserved but not
+            // yet pushed.
+            OverflowCounters::bump(&self.overflow.rejected);
+            return Err(DiplomacyError::QueueFull);
+        }
+    }
+
+    fn admit_blocking(&self, len: usize, priority: Priority) -> Result<Admission, DiplomacyError> {
+        let deadline = match self.config.block_timeout_ms {
+            u32::MAX => None,
+            ms => Some(Instant::now() + Duration::from_millis(u64::from(ms))),
+        };
+
+        let mut guard = self
+            .room_lock
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        loop {
+            if self.is_closed() {
+                return Err(DiplomacyError::Closed);
+            }
+            if self.reserve(len, priority) {
+                return Ok(Admission::Queue);
+            }
+            guard = match deadline {
+                None => self
+                    .room
+                    .wait(guard)
+                    .unwrap_or_else(PoisonError::into_inner),
+                Some(deadline) => {
+                    let Some(remaining) = deadline.checked_duration_since(Instant::now()) else {
+                        OverflowCounters::bump(&self.overflow.timed_out);
+                        return Err(DiplomacyError::QueueFull);
+                    };
+                    self.room
+                        .wait_timeout(guard, remaining)
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .0
+                }
+            };
+        }
+    }
+
+    /// Snapshot of how many envelopes each overflow policy has handled.
+    pub(crate) fn overflow_counts(&self) -> OverflowCounts {
+        self.overflow.snapshot()
+    }
+
+    /// Snapshot of the traffic through this mailbox.
+    pub(crate) fn stats(&self) -> QueueStats {
+        QueueStats {
+            sent: self.sent.load(Ordering::Relaxed),
+            received: self.received.load(Ordering::Relaxed),
+            depth: self.count.load(Ordering::Relaxed),
+            peak_depth: self.peak.load(Ordering::Relaxed),
+            bytes: self.bytes.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Counts an envelope that was delivered without passing through the
+    /// queue, such as one taken by the envoy callback.
+    pub(crate) fn count_delivered(&self) {
+        self.sent.fetch_add(1, Ordering::Relaxed);
+        self.received.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Whether an envelope of `len` payload bytes could ever fit.
+    pub(crate) fn fits(&self, len: usize) -> bool {
+        len <= self.config.max_bytes
+    }
+
+    /// Pushes an envelope into room obtained from [`Mailbox::reserve`] and
+    /// wakes one blocked receiver.
+    pub(crate) fn push(&self, envelope: Envelope) {
+        self.sent.fetch_add(1, Ordering::Relaxed);
+        self.enqueue(envelope);
+    }
+
+    fn enqueue(&self, envelope: Envelope) {
+        self.lanes[envelope.priority.lane()].queue.push(envelope);
+        self.announce();
+    }
+
+    /// Tells the readiness descriptor and one blocked receiver that an
+    /// envelope is waiting.
+    fn announce(&self) {
+        self.sync_readiness();
+        #[cfg(feature = "async")]
+        self.arrivals.wake_all();
+        let _guard = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+        self.ready.notify_one();
+    }
+
+    /// Puts back an envelope that was just popped, bypassing the depth bound.
+    ///
+    /// The envelope goes to the back of its lane and no longer counts as received.
+    pub(crate) fn requeue(&self, envelope: Envelope) {
+        self.received.fetch_sub(1, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.lanes[envelope.priority.lane()]
+            .count
+            .fetch_add(1, Ordering::Relaxed);
+        self.bytes
+            .fetch_add(envelope.payload.len(), Ordering::Relaxed);
+        self.enqueue(envelope);
+    }
+
+    /// Puts back an envelope that was just popped at the head of its lane, so
+    /// it is the next one received from that lane.
+    ///
+    /// Like [`Mailbox::requeue`] this bypasses the depth bound: the envelope
+    /// held its room until a moment ago.
+    pub(crate) fn restore(&self, envelope: Envelope) {
+        let lane = &self.lanes[envelope.priority.lane()];
+        self.received.fetch_sub(1, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+        lane.count.fetch_add(1, Ordering::Relaxed);
+        self.bytes
+            .fetch_add(envelope.payload.len(), Ordering::Relaxed);
+        lane.push_front(envelope);
+        self.announce();
+    }
+
+    /// Takes the oldest envelope of the highest non-empty lane without blocking.
+    ///
+    /// After [`STARVATION_LIMIT`] such receives while a lower lane waits, the
+    /// oldest envelope of the lowest waiting lane is taken instead. Expired
+    /// envelopes are counted, reported and skipped.
+    pub(crate) fn pop(&self) -> Option<Envelope> {
+        loop {
+            let envelope = self.next()?;
+            if !envelope.is_expired() {
+                self.received.fetch_add(1, Ordering::Relaxed);
+                return Some(envelope);
+            }
+            OverflowCounters::bump(&self.overflow.expired);
+            self.expiry.notify(self.direction, envelope);
+        }
+    }
+
+    /// Id and payload length of the envelope [`Mailbox::pop`] would take next,
+    /// leaving it queued. Expired envelopes are counted, reported and skipped.
+    pub(crate) fn peek(&self) -> Option<(u32, usize)> {
+        loop {
+            let lane = self.next_lane(false)?;
+            match self.lanes[lane].peek() {
+                (Some(Ok(head)), moved) => {
+                    if moved {
+                        self.announce();
+                    }
+                    return Some(head);
+                }
+                (Some(Err(envelope)), _) => {
+                    self.release(lane, &envelope);
+                    OverflowCounters::bump(&self.overflow.expired);
+                    self.expiry.notify(self.direction, envelope);
+                }
+                // A concurrent receiver emptied the lane; choose again.
+                (None, _) => {}
+            }
+        }
+    }
+
+    /// Takes the next envelope in priority order, expired or not.
+    fn next(&self) -> Option<Envelope> {
+        let lane = self.next_lane(true)?;
+
+        // A concurrent receiver may have emptied the chosen lane.
+        self.take(lane)
+            .or_else(|| (0..PRIORITY_LANES).rev().find_map(|lane| self.take(lane)))
+    }
+
+    /// The lane the next receive is served from. `advance` counts the receive
+    /// towards the starvation streak; peeking leaves the streak alone.
+    fn next_lane(&self, advance: bool) -> Option<usize> {
+        let waiting = |lane: &Lane| !lane.is_empty();
+        let highest = self.lanes.iter().rposition(waiting)?;
+        let lowest = self.lanes.iter().position(waiting).unwrap_or(highest);
+
+        if lowest == highest {
+            if advance {
+                self.streak.store(0, Ordering::Relaxed);
+            }
+            return Some(highest);
+        }
+        let streak = if advance {
+            self.streak.fetch_add(1, Ordering::Relaxed)
+        } else {
+            self.streak.load(Ordering::Relaxed)
+        };
+        if streak + 1 < STARVATION_LIMIT {
+            return Some(highest);
+        }
+        if advance {
+            self.streak.store(0, Ordering::Relaxed);
+        }
+        Some(lowest)
+    }
+
+    /// Discards an envelope to make room for one of `priority`: from its own
+    /// lane if that lane is full, otherwise from the lowest non-empty lane at
+    /// or below it. Envelopes of a higher priority are never discarded.
+    fn evict(&self, priority: Priority) -> Option<Envelope> {
+        let own = priority.lane();
+        if self.lanes[own].count.load(Ordering::Relaxed) >= self.config.lane_depth[own] {
+            return self.take(own);
+        }
+        (0..=own).find_map(|lane| self.take(lane))
+    }
+
+    /// Removes the queued envelope numbered `sequence` from `priority`'s lane
+    /// and releases its room. Returns `None` if a receiver took it first.
+    ///
+    /// The lane is rotated once, so envelopes pushed meanwhile may overtake
+    /// the rest; only for queues that promise no order between senders.
+    pub(crate) fn withdraw(&self, sequence: u64, priority: Priority) -> Option<Envelope> {
+        let lane = &self.lanes[priority.lane()];
+        let mut withdrawn = None;
+        for _ in 0..lane.queue.len() {
+            let Some(envelope) = lane.queue.pop() else {
+                break;
+            };
+            if withdrawn.is_none() && envelope.sequence == sequence {
+                withdrawn = Some(envelope);
+            } else {
+                lane.queue.push(envelope);
+            }
+        }
+        // Receivers that found the lane empty mid-rotation must look again.
+        {
+            let _guard = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+            self.ready.notify_all();
+        }
+        let envelope = withdrawn?;
+        self.release(priority.lane(), &envelope);
+        Some(envelope)
+    }
+
+    /// Takes the oldest envelope of one lane and releases its room.
+    fn take(&self, lane: usize) -> Option<Envelope> {
+        let envelope = self.lanes[lane].pop()?;
+        self.release(lane, &envelope);
+        Some(envelope)
+    }
+
+    /// Releases the room of an envelope that left `lane`.
+    fn release(&self, lane: usize, envelope: &Envelope) {
+        self.lanes[lane].count.fetch_sub(1, Ordering::Relaxed);
+        self.count.fetch_sub(1, Ordering::Relaxed);
+        self.bytes
+            .fetch_sub(envelope.payload.len(), Ordering::Relaxed);
+        self.sync_readiness();
+        #[cfg(feature = "async")]
+        self.departures.wake_all();
+        if self.config.overflow == OverflowPolicy::BLOCK {
+            let _guard = self
+                .room_lock
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            self.room.notify_all();
+        }
+    }
+
+    /// Async counterpart of [`Mailbox::pop_wait`]: resolves to `None` once closed.
+    #[cfg(feature = "async")]
+    pub(crate) fn poll_pop(&self, cx: &mut Context<'_>) -> Poll<Option<Envelope>> {
+        // Check, register, then check again so a push racing with
+        // registration is never missed.
+        for attempt in 0..2 {
+            if let Some(envelope) = self.pop() {
+                return Poll::Ready(Some(envelope));
+            }
+            if self.is_closed() {
+                return Poll::Ready(None);
+            }
+            if attempt == 0 {
+                self.arrivals.register(cx.waker());
+            }
+        }
+        Poll::Pending
+    }
+
+    /// Waits for room and reserves it, like an async [`Mailbox::reserve`].
+    ///
+    /// Resolves to `false` once the mailbox is closed. Callers must check
+    /// [`Mailbox::fits`] first, or this may wait forever.
+    #[cfg(feature = "async")]
+    pub(crate) fn poll_reserve(
+        &self,
+        cx: &mut Context<'_>,
+        len: usize,
+        priority: Priority,
+    ) -> Poll<bool> {
+        for attempt in 0..2 {
+            if self.is_closed() {
+                return Poll::Ready(false);
+            }
+            if self.reserve(len, priority) {
+                return Poll::Ready(true);
+            }
+            if attempt == 0 {
+                self.departures.register(cx.waker());
+            }
+        }
+        Poll::Pending
+    }
+
+    /// Takes the oldest envelope, waiting until one arrives.
+    ///
+    /// Returns `None` once `deadline` passes or the mailbox is closed.
+    /// A `deadline` of `None` waits indefinitely.
+    pub(crate) fn pop_wait(&self, deadline: Option<Instant>) -> Option<Envelope> {
+        loop {
+            if let Some(envelope) = self.pop() {
+                return Some(envelope);
+            }
+
+            // Popping happens outside the lock, because expiry hooks may send
+            // into this mailbox; checking for envelopes under it is enough not
+            // to miss the wake-up of a concurrent push.
+            let guard = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+            if !self.is_empty() {
+                continue;
+            }
+            if self.is_closed() {
+                return None;
+            }
+            match deadline {
+                None => drop(
+                    self.ready
+                        .wait(guard)
+                        .unwrap_or_else(PoisonError::into_inner),
+                ),
+                Some(deadline) => {
+                    let remaining = deadline.checked_duration_since(Instant::now())?;
+                    drop(
+                        self.ready
+                            .wait_timeout(guard, remaining)
+                            .unwrap_or_else(PoisonError::into_inner),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Descriptor that is readable while the mailbox holds envoys, and hangs
+    /// up once it is closed.
+    ///
+    /// Returns `None` if the descriptor could not be created.
+    #[cfg(unix)]
+    pub(crate) fn ready_fd(&self) -> Option<RawFd> {
+        let readiness = self
+            .readiness
+            .get_or_init(|| {
+                Readiness::new()
+                    .inspect_err(
+                        |e| tracing::error!(error = %e, "Failed to create readiness descriptor"),
+                    )
+                    .ok()
+            })
+            .as_ref()?;
+        if self.is_closed() {
+            readiness.hang_up();
+        } else {
+            readiness.sync(|| !self.is_empty());
+        }
+        Some(readiness.fd())
+    }
+
+    /// Whether the readiness descriptor currently signals pending envoys.
+    #[cfg(all(test, unix))]
+    pub(crate) fn is_ready_signalled(&self) -> bool {
+        matches!(self.readiness.get(), Some(Some(r)) if r.is_signalled())
+    }
+
+    fn sync_readiness(&self) {
+        #[cfg(unix)]
+        if let Some(Some(readiness)) = self.readiness.get() {
+            readiness.sync(|| !self.is_empty());
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.lanes.iter().all(Lane::is_empty)
+    }
+
+    pub(crate) fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
+
+    /// Closes the mailbox, wakes every blocked receiver, hangs up the readiness
+    /// descriptor and discards what is still queued. Returns the number of
+    /// discarded envelopes.
+    pub(crate) fn close(&self) -> usize {
+        {
+            let _guard = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+            self.closed.store(true, Ordering::Release);
+            self.ready.notify_all();
+        }
+        #[cfg(unix)]
+        if let Some(Some(readiness)) = self.readiness.get() {
+            readiness.hang_up();
+        }
+        {
+            let _guard = self
+                .room_lock
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            self.room.notify_all();
+        }
+        #[cfg(feature = "async")]
+        {
+            self.arrivals.wake_all();
+            self.departures.wake_all();
+        }
+
+        let mut discarded = 0;
+        while self.next().is_some() {
+            discarded += 1;
+        }
+        discarded
+    }
+}
+
+/// Wakers of async tasks parked on a mailbox.
+#[cfg(feature = "async")]
+#[derive(Default)]
+struct WakerSet(Mutex<Vec<Waker>>);
+
+#[cfg(feature = "async")]
+impl WakerSet {
+    fn register(&self, waker: &Waker) {
+        let mut wakers = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        if !wakers.iter().any(|w| w.will_wake(waker)) {
+            wakers.push(waker.clone());
+        }
+    }
+
+    fn wake_all(&self) {
+        let wakers = std::mem::take(&mut *self.0.lock().unwrap_or_else(PoisonError::into_inner));
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+}
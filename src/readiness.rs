@@ -0,0 +1,95 @@
+//! Pollable descriptors that signal when a mailbox holds envoys.
+
+use std::io::{ErrorKind, Read, Write};
+use std::net::Shutdown;
+use std::os::fd::{AsRawFd, RawFd};
+use std::os::unix::net::UnixStream;
+use std::sync::{Mutex, PoisonError};
+
+/// A socket pair whose read end is readable exactly while its mailbox is non-empty.
+///
+/// At most one byte is ever in flight: it is written when the mailbox becomes
+/// non-empty and read back when it is drained. Both ends are non-blocking, so
+/// a foreign event loop that reads the byte itself cannot wedge the mailbox;
+/// it only loses the signal until the next transition. Once the mailbox is
+/// closed the descriptor hangs up for good.
+pub(crate) struct Readiness {
+    state: Mutex<ReadinessState>,
+    fd: RawFd,
+}
+
+struct ReadinessState {
+    reader: UnixStream,
+    writer: UnixStream,
+    signalled: bool,
+    hung_up: bool,
+}
+
+impl Readiness {
+    pub(crate) fn new() -> std::io::Result<Self> {
+        let (reader, writer) = UnixStream::pair()?;
+        reader.set_nonblocking(true)?;
+        writer.set_nonblocking(true)?;
+        Ok(Self {
+            fd: reader.as_raw_fd(),
+            state: Mutex::new(ReadinessState {
+                reader,
+                writer,
+                signalled: false,
+                hung_up: false,
+            }),
+        })
+    }
+
+    /// The descriptor foreign event loops should poll for readability.
+    pub(crate) fn fd(&self) -> RawFd {
+        self.fd
+    }
+
+    #[cfg(test)]
+    pub(crate) fn is_signalled(&self) -> bool {
+        self.state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .signalled
+    }
+
+    /// Brings the descriptor in line with whether the mailbox holds envoys.
+    ///
+    /// Callers re-evaluate `non_empty` after every push or pop, so the last
+    /// caller to take the lock always leaves the descriptor correct.
+    pub(crate) fn sync(&self, non_empty: impl FnOnce() -> bool) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let non_empty = non_empty();
+        if state.hung_up || non_empty == state.signalled {
+            return;
+        }
+
+        let result = if non_empty {
+            state.writer.write_all(&[1])
+        } else {
+            let mut byte = [0u8];
+            match state.reader.read(&mut byte) {
+                Err(e) if e.kind() == ErrorKind::WouldBlock => Ok(()),
+                other => other.map(|_| ()),
+            }
+        };
+
+        match result {
+            Ok(()) => state.signalled = non_empty,
+            Err(e) => tracing::error!(error = %e, "Failed to update readiness descriptor"),
+        }
+    }
+
+    /// Makes the descriptor report end-of-file (`POLLHUP`) from now on, so
+    /// that event loops notice the mailbox was closed.
+    pub(crate) fn hang_up(&self) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if std::mem::replace(&mut state.hung_up, true) {
+            return;
+        }
+        if let Err(e) = state.reader.shutdown(Shutdown::Both) {
+            tracing::error!(error = %e, "Failed to hang up readiness descriptor");
+        }
+    }
+}
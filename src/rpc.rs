@@ -0,0 +1,105 @@
+//! Request/response calls from the foreign jurisdiction into Rust.
+
+use crate::envelope::Routing;
+use crate::handler::RpcHandler;
+use crate::safe::DiplomacyError;
+use crate::{Envelope, GlobalRegistry, Priority};
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::sync::mpsc::{self, RecvTimeoutError, SyncSender};
+use std::time::Instant;
+
+/// Where a served call delivers its reply: `None` if the handler panicked.
+pub(crate) type ReplySlot = SyncSender<Option<Envelope>>;
+
+impl GlobalRegistry {
+    /// Queues a call and waits for its reply until `deadline` (or indefinitely if `None`).
+    ///
+    /// The request's sequence number is its correlation id. A caller that
+    /// gives up withdraws its reply slot, so a reply produced afterwards is
+    /// discarded instead of being delivered to a later call, and its request
+    /// if no server has taken it yet, so it stops holding room in the queue.
+    pub(crate) fn call(
+        &self,
+        id: u32,
+        payload: Vec<u8>,
+        deadline: Option<Instant>,
+    ) -> Result<Envelope, DiplomacyError> {
+        if self.is_closed() {
+            return Err(DiplomacyError::Closed);
+        }
+        if !self.calls.reserve(payload.len(), Priority::Normal) {
+            return Err(DiplomacyError::QueueFull);
+        }
+
+        let request = self.seal(id, Routing::default(), payload);
+        let correlation = request.sequence;
+        let (reply_to, reply) = mpsc::sync_channel(1);
+        {
+            let slot = self.pending_calls.entry(correlation).insert(reply_to);
+            // Checked while holding the slot: `sever` marks the registry closed
+            // before dropping every slot, so this caller cannot wait forever.
+            if self.is_closed() {
+                drop(slot);
+                self.pending_calls.remove(&correlation);
+                return Err(DiplomacyError::Closed);
+            }
+        }
+        self.calls.push(request);
+
+        let outcome = match deadline {
+            None => reply.recv().map_err(|_| DiplomacyError::Closed),
+            Some(deadline) => reply
+                .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                .map_err(|e| match e {
+                    RecvTimeoutError::Timeout => DiplomacyError::Timeout,
+                    RecvTimeoutError::Disconnected => DiplomacyError::Closed,
+                }),
+        };
+        if outcome.is_err() {
+            self.pending_calls.remove(&correlation);
+            if self.calls.withdraw(correlation, Priority::Normal).is_some() {
+                tracing::debug!(
+                    envoy_id = id,
+                    correlation,
+                    "Abandoned call withdrawn before it was served"
+                );
+            }
+        }
+        outcome?.ok_or(DiplomacyError::HandlerPanicked)
+    }
+
+    /// Answers calls with `handler` until relations are severed.
+    ///
+    /// Calls whose caller already timed out are skipped.
+    pub(crate) fn serve(&self, handler: &dyn RpcHandler) {
+        while let Some(request) = self.calls.pop_wait(None) {
+            let Some((_, reply_to)) = self.pending_calls.remove(&request.sequence) else {
+                tracing::debug!(
+                    envoy_id = request.id,
+                    correlation = request.sequence,
+                    "Call abandoned before it was served"
+                );
+                continue;
+            };
+
+            let reply = match catch_unwind(AssertUnwindSafe(|| handler.handle(&request))) {
+                Ok(payload) => Some(self.seal(
+                    request.id,
+                    Routing::with_priority(request.priority),
+                    payload,
+                )),
+                Err(_) => {
+                    tracing::error!(envoy_id = request.id, "Panic caught in call handler");
+                    None
+                }
+            };
+            if reply_to.send(reply).is_err() {
+                tracing::debug!(
+                    envoy_id = request.id,
+                    correlation = request.sequence,
+                    "Late reply discarded"
+                );
+            }
+        }
+    }
+}
@@ -1,6 +1,19 @@
-use crate::{GlobalRegistry, REGISTRY};
+#[cfg(feature = "async")]
+use crate::stream::Incoming;
+use crate::{
+    Context, DiplomacyConfig, DiplomacyStats, Envelope, ExpiryHandler, GlobalRegistry, LoanReport,
+    OverflowReport, Priority, ReplyHandler, RpcHandler, SendOptions, SpillHandler,
+    current_registry, install_registry, uninstall_registry,
+};
+#[cfg(unix)]
+use std::os::fd::RawFd;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-#[derive(thiserror::Error, Debug, PartialEq)]
+/// Why a diplomatic operation failed.
+///
+/// Each variant has a C status code; see [`PraborrowStatus`](crate::PraborrowStatus).
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DiplomacyError {
     #[error("Already initialized")]
     AlreadyInitialized,
@@ -10,6 +23,62 @@ pub enum DiplomacyError {
     NotInitialized,
     #[error("Queue capacity exceeded")]
     QueueFull,
+    #[error("Relations severed")]
+    Closed,
+    #[error("Timed out waiting for an envoy")]
+    Timeout,
+    #[error("Readiness descriptor unavailable")]
+    FdUnavailable,
+    #[error("Invalid configuration")]
+    InvalidConfig,
+    #[error("Handler panicked")]
+    HandlerPanicked,
+    #[error("Invalid priority")]
+    InvalidPriority,
+    #[error("Null pointer")]
+    NullPointer,
+    #[error("Invalid UTF-8")]
+    InvalidUtf8,
+    #[error("Invalid envoy id")]
+    InvalidId,
+    #[error("Buffer too small")]
+    BufferTooSmall,
+    #[error("Invalid envoy handle")]
+    InvalidHandle,
+    #[error("Envoy contains a NUL byte")]
+    ContainsNul,
+}
+
+/// A batch send that stopped part-way.
+///
+/// The first `sent` envoys were queued; the next one failed with `error` and
+/// nothing after it was attempted.
+#[derive(thiserror::Error, Debug, PartialEq)]
+#[error("Batch stopped after {sent} envoys: {error}")]
+pub struct BatchError {
+    pub sent: usize,
+    pub error: DiplomacyError,
+}
+
+/// What was left behind when diplomatic relations were severed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SeveranceReport {
+    /// Envoys from the foreign jurisdiction that were never received by Rust.
+    pub discarded_incoming: usize,
+    /// Envoys for the foreign jurisdiction that were never received by C.
+    pub discarded_outbox: usize,
+    /// Pointers handed out by `receive_envoy` and never passed to `free_envoy`,
+    /// plus handles from `receive_envoy_handle` never passed to `envoy_release`.
+    pub leaked_loans: usize,
+}
+
+impl SeveranceReport {
+    /// Adds what a severed channel left behind.
+    pub(crate) fn merge(&mut self, other: SeveranceReport) {
+        self.discarded_incoming += other.discarded_incoming;
+        self.discarded_outbox += other.discarded_outbox;
+        self.leaked_loans += other.leaked_loans;
+    }
 }
 
 /// A safe wrapper for the Diplomatic Relations FFI.
@@ -23,52 +92,521 @@ impl Diplomat {
     ///
     /// This effectively calls the internal `establish_relations` logic safely.
     pub fn init() -> Result<(), DiplomacyError> {
-        if REGISTRY.get().is_some() {
-            return Err(DiplomacyError::AlreadyInitialized);
-        }
-        match REGISTRY.set(GlobalRegistry::new()) {
-            Ok(_) => {
-                tracing::info!("Diplomatic relations established via Safe Wrapper");
-                Ok(())
-            }
-            Err(_) => Err(DiplomacyError::InitFailed),
+        Self::init_with_config(DiplomacyConfig::default())
+    }
+
+    /// Initializes the diplomatic registry with custom queue limits.
+    pub fn init_with_config(config: DiplomacyConfig) -> Result<(), DiplomacyError> {
+        install_registry(config)?;
+        tracing::info!("Diplomatic relations established via Safe Wrapper");
+        Ok(())
+    }
+
+    /// Severs diplomatic relations, ending the current session.
+    ///
+    /// Queued envoys in both directions are discarded and in-flight sends are
+    /// rejected with [`DiplomacyError::Closed`]. A later [`Diplomat::init`]
+    /// starts a fresh session.
+    pub fn shutdown() -> Result<SeveranceReport, DiplomacyError> {
+        let report = uninstall_registry()?;
+        if report.leaked_loans > 0 {
+            tracing::warn!(
+                leaked_loans = report.leaked_loans,
+                "Relations severed with envoys still on loan"
+            );
         }
+        tracing::info!("Diplomatic relations severed via Safe Wrapper");
+        Ok(report)
+    }
+
+    /// Opens the channel called `name` on the default context.
+    ///
+    /// The channel has its own queues, limits, handlers and counters; opening
+    /// the same name again, from Rust or with `praborrow_channel_open`, yields
+    /// the same channel.
+    pub fn channel(name: &str) -> Result<Embassy, DiplomacyError> {
+        Self::channel_with_config(name, DiplomacyConfig::default())
+    }
+
+    /// Opens the channel called `name`, creating it with custom queue limits.
+    ///
+    /// `config` only applies if the channel does not exist yet.
+    pub fn channel_with_config(
+        name: &str,
+        config: DiplomacyConfig,
+    ) -> Result<Embassy, DiplomacyError> {
+        let registry = current_registry().ok_or(DiplomacyError::NotInitialized)?;
+        Ok(Embassy {
+            registry: registry.open_channel(name, config)?,
+        })
+    }
+
+    /// Installs the handler that decides the reply to each envoy from C.
+    ///
+    /// Replaces any previously installed handler. Without a handler, envoys
+    /// are delivered to Rust and nothing is sent back.
+    pub fn set_reply_handler(handler: impl ReplyHandler + 'static) -> Result<(), DiplomacyError> {
+        let registry = current_registry().ok_or(DiplomacyError::NotInitialized)?;
+        registry.set_reply_handler(Some(Arc::new(handler)));
+        Ok(())
+    }
+
+    /// Removes the reply handler, so envoys from C are no longer answered.
+    pub fn clear_reply_handler() -> Result<(), DiplomacyError> {
+        let registry = current_registry().ok_or(DiplomacyError::NotInitialized)?;
+        registry.set_reply_handler(None);
+        Ok(())
+    }
+
+    /// Installs the handler that takes envoys overflowing a queue configured
+    /// with [`OverflowPolicy::SPILL`](crate::OverflowPolicy::SPILL).
+    pub fn set_spill_handler(handler: impl SpillHandler + 'static) -> Result<(), DiplomacyError> {
+        let registry = current_registry().ok_or(DiplomacyError::NotInitialized)?;
+        registry.set_spill_handler(Some(Arc::new(handler)));
+        Ok(())
+    }
+
+    /// Removes the spill handler, so spilling queues reject when full.
+    pub fn clear_spill_handler() -> Result<(), DiplomacyError> {
+        let registry = current_registry().ok_or(DiplomacyError::NotInitialized)?;
+        registry.set_spill_handler(None);
+        Ok(())
+    }
+
+    /// How many envoys each overflow policy has handled in this session.
+    pub fn overflow_report() -> Result<OverflowReport, DiplomacyError> {
+        let registry = current_registry().ok_or(DiplomacyError::NotInitialized)?;
+        Ok(registry.overflow_report())
+    }
+
+    /// The envoys received by the foreign jurisdiction and not yet returned.
+    pub fn loan_report() -> Result<LoanReport, DiplomacyError> {
+        let registry = current_registry().ok_or(DiplomacyError::NotInitialized)?;
+        Ok(registry.loans.report())
+    }
+
+    /// Traffic, queue depths, loans and failed calls of this session.
+    pub fn stats() -> Result<DiplomacyStats, DiplomacyError> {
+        let registry = current_registry().ok_or(DiplomacyError::NotInitialized)?;
+        Ok(registry.stats())
+    }
+
+    /// Answers `praborrow_call` requests with `handler` until relations are severed.
+    ///
+    /// Blocks the calling thread; run it on a dedicated thread. Several
+    /// threads may serve the same context concurrently.
+    pub fn serve(handler: impl RpcHandler) -> Result<(), DiplomacyError> {
+        let registry = current_registry().ok_or(DiplomacyError::NotInitialized)?;
+        registry.serve(&handler);
+        Ok(())
     }
 
     /// Sends a message TO the foreign jurisdiction (C world).
     ///
-    /// Pushes the message to the internal outbox, formatted as "{id}:{payload}".
+    /// Pushes an [`Envelope`] carrying `id` and `payload` to the internal outbox.
     /// C-side `receive_envoy` will pop this message.
     pub fn send(id: u32, payload: &str) -> Result<(), DiplomacyError> {
-        let registry = REGISTRY.get().ok_or(DiplomacyError::NotInitialized)?;
-
-        // OOM Check
-        if registry
-            .outbox_count
-            .fetch_add(1, std::sync::atomic::Ordering::Relaxed)
-            >= crate::MAX_QUEUE_DEPTH
-        {
-            registry
-                .outbox_count
-                .fetch_sub(1, std::sync::atomic::Ordering::Relaxed);
-            return Err(DiplomacyError::QueueFull);
-        }
+        Self::send_bytes(id, payload.as_bytes())
+    }
+
+    /// Sends a binary message TO the foreign jurisdiction (C world).
+    ///
+    /// C-side `receive_envoy_bytes` will pop this message with its exact length.
+    pub fn send_bytes(id: u32, payload: &[u8]) -> Result<(), DiplomacyError> {
+        Self::send_bytes_priority(id, Priority::Normal, payload)
+    }
+
+    /// Sends a message TO the foreign jurisdiction in a priority lane.
+    ///
+    /// C-side receives take higher lanes first; [`Diplomat::send`] uses
+    /// [`Priority::Normal`].
+    pub fn send_priority(id: u32, priority: Priority, payload: &str) -> Result<(), DiplomacyError> {
+        Self::send_bytes_priority(id, priority, payload.as_bytes())
+    }
+
+    /// Sends a binary message TO the foreign jurisdiction in a priority lane.
+    pub fn send_bytes_priority(
+        id: u32,
+        priority: Priority,
+        payload: &[u8],
+    ) -> Result<(), DiplomacyError> {
+        Self::send_with(id, payload, SendOptions::new().priority(priority))
+    }
+
+    /// Sends several binary messages TO the foreign jurisdiction in order.
+    ///
+    /// Stops at the first message that cannot be sent; the [`BatchError`]
+    /// says how many were sent before it. Returns the number sent.
+    pub fn send_batch<I, P>(envoys: I) -> Result<usize, BatchError>
+    where
+        I: IntoIterator<Item = (u32, P)>,
+        P: AsRef<[u8]>,
+    {
+        let registry = current_registry().ok_or(BatchError {
+            sent: 0,
+            error: DiplomacyError::NotInitialized,
+        })?;
+        registry.dispatch_batch(envoys)
+    }
 
-        registry.outbox.push(format!("{}:{}", id, payload));
+    /// Sends a binary message TO the foreign jurisdiction with delivery options.
+    ///
+    /// An envoy whose TTL or deadline passes before C-side `receive_envoy`
+    /// takes it is skipped and counted as expired.
+    pub fn send_with(id: u32, payload: &[u8], options: SendOptions) -> Result<(), DiplomacyError> {
+        let registry = current_registry().ok_or(DiplomacyError::NotInitialized)?;
+        registry.dispatch(id, options.resolve()?, payload)
+    }
+
+    /// Installs the handler told about envoys that expire while queued.
+    pub fn set_expiry_handler(handler: impl ExpiryHandler + 'static) -> Result<(), DiplomacyError> {
+        let registry = current_registry().ok_or(DiplomacyError::NotInitialized)?;
+        registry.expiry.set_handler(Some(Arc::new(handler)));
         Ok(())
     }
 
+    /// Removes the expiry handler; expired envoys are still counted and logged.
+    pub fn clear_expiry_handler() -> Result<(), DiplomacyError> {
+        let registry = current_registry().ok_or(DiplomacyError::NotInitialized)?;
+        registry.expiry.set_handler(None);
+        Ok(())
+    }
+
+    /// Sends a message TO the foreign jurisdiction, waiting for outbox capacity.
+    ///
+    /// Unlike [`Diplomat::send`], a full outbox does not fail with
+    /// [`DiplomacyError::QueueFull`]; the future resolves once C-side
+    /// `receive_envoy` makes room, or fails with [`DiplomacyError::Closed`] if
+    /// relations are severed first.
+    #[cfg(feature = "async")]
+    pub async fn send_async(id: u32, payload: &str) -> Result<(), DiplomacyError> {
+        Self::send_bytes_async(id, payload.as_bytes()).await
+    }
+
+    /// Binary counterpart of [`Diplomat::send_async`].
+    #[cfg(feature = "async")]
+    pub async fn send_bytes_async(id: u32, payload: &[u8]) -> Result<(), DiplomacyError> {
+        let registry = current_registry().ok_or(DiplomacyError::NotInitialized)?;
+        registry.dispatch_async(id, payload).await
+    }
+
+    /// Returns a stream of messages FROM the foreign jurisdiction.
+    ///
+    /// The stream is woken by C-side `send_envoy` and ends when relations are
+    /// severed.
+    #[cfg(feature = "async")]
+    pub fn incoming() -> Result<Incoming, DiplomacyError> {
+        let registry = current_registry().ok_or(DiplomacyError::NotInitialized)?;
+        Ok(Incoming::new(registry))
+    }
+
     /// Receives a message FROM the foreign jurisdiction (C world).
     ///
-    /// Pops from the internal incoming queue, which is populated by C-side `send_envoy`.
+    /// Pops from the internal incoming queue, which is populated by C-side `send_envoy`,
+    /// and returns only the payload. Payloads that are not valid UTF-8 are
+    /// converted lossily; use [`Diplomat::receive_bytes`] for binary traffic.
     pub fn receive() -> Option<String> {
-        let registry = REGISTRY.get()?;
-        let msg = registry.incoming_envoys.pop();
-        if msg.is_some() {
-            registry
-                .incoming_count
-                .fetch_sub(1, std::sync::atomic::Ordering::Relaxed);
+        Self::receive_bytes().map(into_string_lossy)
+    }
+
+    /// Receives a binary message FROM the foreign jurisdiction (C world).
+    ///
+    /// Pops from the internal incoming queue, populated by `send_envoy` or `send_envoy_bytes`.
+    pub fn receive_bytes() -> Option<Vec<u8>> {
+        Self::receive_envelope().map(|envelope| envelope.payload)
+    }
+
+    /// Receives a message FROM the foreign jurisdiction (C world) with its metadata.
+    pub fn receive_envelope() -> Option<Envelope> {
+        current_registry()?.take_incoming()
+    }
+
+    /// Receives up to `max` messages FROM the foreign jurisdiction without blocking.
+    pub fn drain(max: usize) -> Vec<Envelope> {
+        current_registry()
+            .map(|registry| registry.drain_incoming(max))
+            .unwrap_or_default()
+    }
+
+    /// Receives a message FROM the foreign jurisdiction, waiting up to `timeout`.
+    ///
+    /// Woken by C-side `send_envoy`. Fails with [`DiplomacyError::Timeout`] if
+    /// nothing arrives in time and [`DiplomacyError::Closed`] if relations are
+    /// severed while waiting.
+    pub fn recv_timeout(timeout: Duration) -> Result<Envelope, DiplomacyError> {
+        let registry = current_registry().ok_or(DiplomacyError::NotInitialized)?;
+        registry.wait_incoming(Some(Instant::now() + timeout))
+    }
+
+    /// Returns a descriptor that is readable while envoys from C are waiting.
+    ///
+    /// Lets Rust reactors (mio, tokio's `AsyncFd`) wait for `send_envoy` without
+    /// a dedicated thread. Do not read from it. Severing relations closes it,
+    /// so deregister it from the reactor first.
+    #[cfg(unix)]
+    pub fn incoming_fd() -> Result<RawFd, DiplomacyError> {
+        let registry = current_registry().ok_or(DiplomacyError::NotInitialized)?;
+        registry
+            .incoming
+            .ready_fd()
+            .ok_or(DiplomacyError::FdUnavailable)
+    }
+
+    /// Receives a message FROM the foreign jurisdiction, waiting as long as it takes.
+    ///
+    /// Returns [`DiplomacyError::Closed`] once relations are severed.
+    pub fn recv_blocking() -> Result<Envelope, DiplomacyError> {
+        let registry = current_registry().ok_or(DiplomacyError::NotInitialized)?;
+        registry.wait_incoming(None)
+    }
+}
+
+fn into_string_lossy(bytes: Vec<u8>) -> String {
+    String::from_utf8(bytes).unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into_owned())
+}
+
+/// An independent diplomatic context owned by Rust.
+///
+/// Where [`Diplomat`] talks to the process-wide default context, each
+/// `Embassy` has its own inbox and outbox. Foreign code reaches it through the
+/// `*_ctx` functions. Clones share the same context.
+#[derive(Clone)]
+pub struct Embassy {
+    registry: Arc<GlobalRegistry>,
+}
+
+impl Embassy {
+    /// Opens a new, empty context.
+    pub fn new() -> Self {
+        Self {
+            registry: Arc::new(GlobalRegistry::new()),
         }
-        msg
+    }
+
+    /// Opens a new, empty context with custom queue limits.
+    pub fn with_config(config: DiplomacyConfig) -> Result<Self, DiplomacyError> {
+        config.validate()?;
+        Ok(Self {
+            registry: Arc::new(GlobalRegistry::with_config(config)),
+        })
+    }
+
+    /// Attaches to a context created on the foreign side with `praborrow_context_new`.
+    ///
+    /// Returns `None` if `ctx` is null.
+    ///
+    /// # Safety
+    /// `ctx` must be null or a live pointer returned by `praborrow_context_new`.
+    pub unsafe fn from_context(ctx: *const Context) -> Option<Self> {
+        let ctx = unsafe { Context::from_ptr(ctx) }?;
+        Some(Self {
+            registry: Arc::clone(ctx.registry()),
+        })
+    }
+
+    /// Creates a foreign handle to this context for use with the `*_ctx` functions.
+    ///
+    /// The foreign side owns the handle and must release it with
+    /// `praborrow_context_free`, which also severs the context.
+    pub fn context(&self) -> *mut Context {
+        Box::into_raw(Box::new(Context::new(Arc::clone(&self.registry))))
+    }
+
+    /// Opens the channel called `name` on this context.
+    ///
+    /// Fails with [`DiplomacyError::Closed`] once the context is shut down.
+    pub fn channel(&self, name: &str) -> Result<Embassy, DiplomacyError> {
+        self.channel_with_config(name, DiplomacyConfig::default())
+    }
+
+    /// Opens the channel called `name` on this context, creating it with
+    /// custom queue limits.
+    pub fn channel_with_config(
+        &self,
+        name: &str,
+        config: DiplomacyConfig,
+    ) -> Result<Embassy, DiplomacyError> {
+        Ok(Embassy {
+            registry: self.registry.open_channel(name, config)?,
+        })
+    }
+
+    /// Installs the handler that decides the reply to each envoy from C.
+    pub fn set_reply_handler(&self, handler: impl ReplyHandler + 'static) {
+        self.registry.set_reply_handler(Some(Arc::new(handler)));
+    }
+
+    /// Removes the reply handler, so envoys from C are no longer answered.
+    pub fn clear_reply_handler(&self) {
+        self.registry.set_reply_handler(None);
+    }
+
+    /// Installs the handler that takes envoys overflowing a spilling queue.
+    pub fn set_spill_handler(&self, handler: impl SpillHandler + 'static) {
+        self.registry.set_spill_handler(Some(Arc::new(handler)));
+    }
+
+    /// Removes the spill handler, so spilling queues reject when full.
+    pub fn clear_spill_handler(&self) {
+        self.registry.set_spill_handler(None);
+    }
+
+    /// How many envoys each overflow policy of this context has handled.
+    pub fn overflow_report(&self) -> OverflowReport {
+        self.registry.overflow_report()
+    }
+
+    /// The envoys of this context received by the foreign jurisdiction and
+    /// not yet returned.
+    pub fn loan_report(&self) -> LoanReport {
+        self.registry.loans.report()
+    }
+
+    /// Traffic, queue depths, loans and failed calls of this context.
+    pub fn stats(&self) -> DiplomacyStats {
+        self.registry.stats()
+    }
+
+    /// Answers `praborrow_call_ctx` requests with `handler` until this context
+    /// is shut down. Blocks the calling thread.
+    pub fn serve(&self, handler: impl RpcHandler) {
+        self.registry.serve(&handler);
+    }
+
+    /// Sends a message TO the foreign jurisdiction through this context.
+    ///
+    /// C-side `receive_envoy_ctx` will pop this message.
+    pub fn send(&self, id: u32, payload: &str) -> Result<(), DiplomacyError> {
+        self.send_bytes(id, payload.as_bytes())
+    }
+
+    /// Sends a binary message TO the foreign jurisdiction through this context.
+    pub fn send_bytes(&self, id: u32, payload: &[u8]) -> Result<(), DiplomacyError> {
+        self.send_bytes_priority(id, Priority::Normal, payload)
+    }
+
+    /// Sends a message TO the foreign jurisdiction through this context in a priority lane.
+    pub fn send_priority(
+        &self,
+        id: u32,
+        priority: Priority,
+        payload: &str,
+    ) -> Result<(), DiplomacyError> {
+        self.send_bytes_priority(id, priority, payload.as_bytes())
+    }
+
+    /// Sends a binary message TO the foreign jurisdiction through this context
+    /// in a priority lane.
+    pub fn send_bytes_priority(
+        &self,
+        id: u32,
+        priority: Priority,
+        payload: &[u8],
+    ) -> Result<(), DiplomacyError> {
+        self.send_with(id, payload, SendOptions::new().priority(priority))
+    }
+
+    /// Sends several binary messages TO the foreign jurisdiction through this
+    /// context in order, stopping at the first that cannot be sent.
+    pub fn send_batch<I, P>(&self, envoys: I) -> Result<usize, BatchError>
+    where
+        I: IntoIterator<Item = (u32, P)>,
+        P: AsRef<[u8]>,
+    {
+        self.registry.dispatch_batch(envoys)
+    }
+
+    /// Sends a binary message TO the foreign jurisdiction through this context
+    /// with delivery options.
+    pub fn send_with(
+        &self,
+        id: u32,
+        payload: &[u8],
+        options: SendOptions,
+    ) -> Result<(), DiplomacyError> {
+        self.registry.dispatch(id, options.resolve()?, payload)
+    }
+
+    /// Installs the handler told about envoys that expire while queued.
+    pub fn set_expiry_handler(&self, handler: impl ExpiryHandler + 'static) {
+        self.registry.expiry.set_handler(Some(Arc::new(handler)));
+    }
+
+    /// Removes the expiry handler; expired envoys are still counted and logged.
+    pub fn clear_expiry_handler(&self) {
+        self.registry.expiry.set_handler(None);
+    }
+
+    /// Sends a message through this context, waiting for outbox capacity.
+    #[cfg(feature = "async")]
+    pub async fn send_async(&self, id: u32, payload: &str) -> Result<(), DiplomacyError> {
+        self.send_bytes_async(id, payload.as_bytes()).await
+    }
+
+    /// Binary counterpart of [`Embassy::send_async`].
+    #[cfg(feature = "async")]
+    pub async fn send_bytes_async(&self, id: u32, payload: &[u8]) -> Result<(), DiplomacyError> {
+        self.registry.dispatch_async(id, payload).await
+    }
+
+    /// Returns a stream of messages FROM the foreign jurisdiction through this context.
+    #[cfg(feature = "async")]
+    pub fn incoming(&self) -> Incoming {
+        Incoming::new(Arc::clone(&self.registry))
+    }
+
+    /// Receives a message FROM the foreign jurisdiction through this context.
+    ///
+    /// Pops from the queue populated by C-side `send_envoy_ctx`. Payloads that
+    /// are not valid UTF-8 are converted lossily.
+    pub fn receive(&self) -> Option<String> {
+        self.receive_bytes().map(into_string_lossy)
+    }
+
+    /// Receives a binary message FROM the foreign jurisdiction through this context.
+    pub fn receive_bytes(&self) -> Option<Vec<u8>> {
+        self.receive_envelope().map(|envelope| envelope.payload)
+    }
+
+    /// Receives a message FROM the foreign jurisdiction through this context with its metadata.
+    pub fn receive_envelope(&self) -> Option<Envelope> {
+        self.registry.take_incoming()
+    }
+
+    /// Receives up to `max` messages through this context without blocking.
+    pub fn drain(&self, max: usize) -> Vec<Envelope> {
+        self.registry.drain_incoming(max)
+    }
+
+    /// Receives a message through this context, waiting up to `timeout`.
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<Envelope, DiplomacyError> {
+        self.registry.wait_incoming(Some(Instant::now() + timeout))
+    }
+
+    /// Returns a descriptor that is readable while envoys from C are waiting
+    /// on this context, and reports end-of-file once it is severed.
+    #[cfg(unix)]
+    pub fn incoming_fd(&self) -> Result<RawFd, DiplomacyError> {
+        self.registry
+            .incoming
+            .ready_fd()
+            .ok_or(DiplomacyError::FdUnavailable)
+    }
+
+    /// Receives a message through this context, waiting until one arrives or
+    /// the context is severed.
+    pub fn recv_blocking(&self) -> Result<Envelope, DiplomacyError> {
+        self.registry.wait_incoming(None)
+    }
+
+    /// Severs this context for every holder, discarding queued envoys.
+    pub fn shutdown(&self) -> SeveranceReport {
+        self.registry.sever()
+    }
+}
+
+impl Default for Embassy {
+    fn default() -> Self {
+        Self::new()
     }
 }
@@ -0,0 +1,116 @@
+//! Counters describing how diplomatic queues behave under load.
+
+use crate::status::PraborrowStatus;
+use std::os::raw::c_int;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Length of [`DiplomacyStats::rejects`]: one slot per failure code, indexed
+/// by the code's magnitude.
+pub const REJECT_CODES: usize = 17;
+
+const _: () = assert!(REJECT_CODES == (1 - PraborrowStatus::ContainsNul.code()) as usize);
+
+/// C calls that failed, by code, since the process started.
+static REJECTS: [AtomicU64; REJECT_CODES] = [const { AtomicU64::new(0) }; REJECT_CODES];
+
+/// Envoys a single queue turned away, diverted or discarded instead of
+/// delivering them, broken down by cause.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct OverflowCounts {
+    /// New envoys refused outright (`REJECT`, or `SPILL` without a handler).
+    pub rejected: u64,
+    /// Queued envoys discarded to make room (`DROP_OLDEST`).
+    pub dropped_oldest: u64,
+    /// New envoys refused after waiting for room (`BLOCK`).
+    pub timed_out: u64,
+    /// New envoys handed to the spill handler (`SPILL`).
+    pub spilled: u64,
+    /// Queued envoys skipped on dequeue because their TTL or deadline passed.
+    pub expired: u64,
+}
+
+/// Discard counters of both queues of a context since it was created.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct OverflowReport {
+    /// Envoys from the foreign jurisdiction.
+    pub incoming: OverflowCounts,
+    /// Envoys for the foreign jurisdiction.
+    pub outbox: OverflowCounts,
+}
+
+/// Live counterpart of [`OverflowCounts`], updated by a mailbox.
+#[derive(Default)]
+pub(crate) struct OverflowCounters {
+    pub(crate) rejected: AtomicU64,
+    pub(crate) dropped_oldest: AtomicU64,
+    pub(crate) timed_out: AtomicU64,
+    pub(crate) spilled: AtomicU64,
+    pub(crate) expired: AtomicU64,
+}
+
+impl OverflowCounters {
+    pub(crate) fn bump(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> OverflowCounts {
+        OverflowCounts {
+            rejected: self.rejected.load(Ordering::Relaxed),
+            dropped_oldest: self.dropped_oldest.load(Ordering::Relaxed),
+            timed_out: self.timed_out.load(Ordering::Relaxed),
+            spilled: self.spilled.load(Ordering::Relaxed),
+            expired: self.expired.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Traffic through a single queue (the incoming queue or the outbox).
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct QueueStats {
+    /// Envoys accepted into the queue, or delivered straight to the envoy callback.
+    pub sent: u64,
+    /// Envoys handed to a receiver. Expired and discarded envoys are not counted.
+    pub received: u64,
+    /// Envoys queued right now.
+    pub depth: usize,
+    /// Highest `depth` so far.
+    pub peak_depth: usize,
+    /// Payload bytes queued right now.
+    pub bytes: usize,
+}
+
+/// Snapshot of the traffic and health of a context.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DiplomacyStats {
+    /// Envoys from the foreign jurisdiction.
+    pub incoming: QueueStats,
+    /// Envoys for the foreign jurisdiction.
+    pub outbox: QueueStats,
+    /// Envoys on loan to the foreign jurisdiction, by pointer or by handle.
+    pub outstanding_loans: usize,
+    /// Frees and releases of envoys that were not on loan: double frees,
+    /// stale handles and pointers this library never handed out.
+    pub double_frees: u64,
+    /// C calls that failed, by status: `rejects[n]` counts calls that
+    /// returned `-n`. Counted for the whole process rather than per context,
+    /// as some failures (such as a NULL context) belong to none.
+    pub rejects: [u64; REJECT_CODES],
+}
+
+/// Counts a C call that failed with `code`.
+pub(crate) fn count_reject(code: c_int) {
+    if code < 0
+        && let Some(slot) = REJECTS.get(code.unsigned_abs() as usize)
+    {
+        slot.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Failed C calls so far, indexed like [`DiplomacyStats::rejects`].
+pub(crate) fn rejects() -> [u64; REJECT_CODES] {
+    std::array::from_fn(|code| REJECTS[code].load(Ordering::Relaxed))
+}
@@ -0,0 +1,166 @@
+//! Status codes returned by the C functions.
+
+use crate::safe::DiplomacyError;
+use std::ffi::CStr;
+use std::os::raw::c_int;
+
+/// Result of a C call: `0` or `1` on success, a negative code on failure.
+///
+/// Functions return these values as plain `int32_t` so that counts and
+/// descriptors can share the return value; compare them against the
+/// `PRABORROW_STATUS_*` constants. Every failure corresponds to exactly one
+/// [`DiplomacyError`] and back.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PraborrowStatus {
+    /// The call succeeded.
+    Success = 0,
+    /// Nothing to receive.
+    Empty = 1,
+    /// Relations are already established.
+    AlreadyInitialized = -1,
+    /// Relations could not be established.
+    InitFailed = -2,
+    /// A required pointer argument is NULL.
+    NullPointer = -3,
+    /// A text payload is not valid UTF-8.
+    InvalidUtf8 = -4,
+    /// Envoy id 0 is reserved.
+    InvalidId = -5,
+    /// Rust code panicked while serving the call.
+    Panicked = -6,
+    /// The queue is full.
+    QueueFull = -7,
+    /// Relations were severed.
+    Closed = -8,
+    /// No readiness descriptor is available on this platform.
+    FdUnavailable = -9,
+    /// A queue limit is zero, or an overflow policy or payload log mode is unknown.
+    InvalidConfig = -10,
+    /// No envoy or reply arrived in time.
+    Timeout = -11,
+    /// A priority names no lane.
+    InvalidPriority = -12,
+    /// The caller's buffer cannot hold the envoy.
+    BufferTooSmall = -13,
+    /// An envoy handle is not on loan.
+    InvalidHandle = -14,
+    /// Relations are not established. Before ABI version 5, calls made before
+    /// `establish_relations` returned [`PraborrowStatus::InitFailed`] instead.
+    NotInitialized = -15,
+    /// An envoy contains a NUL byte and cannot be received as a C string.
+    ContainsNul = -16,
+}
+
+impl PraborrowStatus {
+    const ALL: [Self; 18] = [
+        Self::Success,
+        Self::Empty,
+        Self::AlreadyInitialized,
+        Self::InitFailed,
+        Self::NullPointer,
+        Self::InvalidUtf8,
+        Self::InvalidId,
+        Self::Panicked,
+        Self::QueueFull,
+        Self::Closed,
+        Self::FdUnavailable,
+        Self::InvalidConfig,
+        Self::Timeout,
+        Self::InvalidPriority,
+        Self::BufferTooSmall,
+        Self::InvalidHandle,
+        Self::NotInitialized,
+        Self::ContainsNul,
+    ];
+
+    /// The value C functions return for this status.
+    pub const fn code(self) -> c_int {
+        self as c_int
+    }
+
+    /// The status a C function reported with `code`, if it is one.
+    pub fn from_code(code: c_int) -> Option<Self> {
+        Self::ALL.into_iter().find(|status| status.code() == code)
+    }
+
+    /// Short description, the same text as the matching [`DiplomacyError`].
+    pub const fn description(self) -> &'static CStr {
+        match self {
+            Self::Success => c"Success",
+            Self::Empty => c"No envoy available",
+            Self::AlreadyInitialized => c"Already initialized",
+            Self::InitFailed => c"Initialization failed",
+            Self::NullPointer => c"Null pointer",
+            Self::InvalidUtf8 => c"Invalid UTF-8",
+            Self::InvalidId => c"Invalid envoy id",
+            Self::Panicked => c"Handler panicked",
+            Self::QueueFull => c"Queue capacity exceeded",
+            Self::Closed => c"Relations severed",
+            Self::FdUnavailable => c"Readiness descriptor unavailable",
+            Self::InvalidConfig => c"Invalid configuration",
+            Self::Timeout => c"Timed out waiting for an envoy",
+            Self::InvalidPriority => c"Invalid priority",
+            Self::BufferTooSmall => c"Buffer too small",
+            Self::InvalidHandle => c"Invalid envoy handle",
+            Self::NotInitialized => c"Registry not initialized",
+            Self::ContainsNul => c"Envoy contains a NUL byte",
+        }
+    }
+}
+
+impl From<DiplomacyError> for PraborrowStatus {
+    fn from(error: DiplomacyError) -> Self {
+        match error {
+            DiplomacyError::AlreadyInitialized => Self::AlreadyInitialized,
+            DiplomacyError::InitFailed => Self::InitFailed,
+            DiplomacyError::NotInitialized => Self::NotInitialized,
+            DiplomacyError::QueueFull => Self::QueueFull,
+            DiplomacyError::Closed => Self::Closed,
+            DiplomacyError::Timeout => Self::Timeout,
+            DiplomacyError::FdUnavailable => Self::FdUnavailable,
+            DiplomacyError::InvalidConfig => Self::InvalidConfig,
+            DiplomacyError::HandlerPanicked => Self::Panicked,
+            DiplomacyError::InvalidPriority => Self::InvalidPriority,
+            DiplomacyError::NullPointer => Self::NullPointer,
+            DiplomacyError::InvalidUtf8 => Self::InvalidUtf8,
+            DiplomacyError::InvalidId => Self::InvalidId,
+            DiplomacyError::BufferTooSmall => Self::BufferTooSmall,
+            DiplomacyError::InvalidHandle => Self::InvalidHandle,
+            DiplomacyError::ContainsNul => Self::ContainsNul,
+        }
+    }
+}
+
+impl TryFrom<PraborrowStatus> for DiplomacyError {
+    /// The status was a success.
+    type Error = PraborrowStatus;
+
+    fn try_from(status: PraborrowStatus) -> Result<Self, PraborrowStatus> {
+        Ok(match status {
+            PraborrowStatus::Success | PraborrowStatus::Empty => return Err(status),
+            PraborrowStatus::AlreadyInitialized => Self::AlreadyInitialized,
+            PraborrowStatus::InitFailed => Self::InitFailed,
+            PraborrowStatus::NullPointer => Self::NullPointer,
+            PraborrowStatus::InvalidUtf8 => Self::InvalidUtf8,
+            PraborrowStatus::InvalidId => Self::InvalidId,
+            PraborrowStatus::Panicked => Self::HandlerPanicked,
+            PraborrowStatus::QueueFull => Self::QueueFull,
+            PraborrowStatus::Closed => Self::Closed,
+            PraborrowStatus::FdUnavailable => Self::FdUnavailable,
+            PraborrowStatus::InvalidConfig => Self::InvalidConfig,
+            PraborrowStatus::Timeout => Self::Timeout,
+            PraborrowStatus::InvalidPriority => Self::InvalidPriority,
+            PraborrowStatus::BufferTooSmall => Self::BufferTooSmall,
+            PraborrowStatus::InvalidHandle => Self::InvalidHandle,
+            PraborrowStatus::NotInitialized => Self::NotInitialized,
+            PraborrowStatus::ContainsNul => Self::ContainsNul,
+        })
+    }
+}
+
+impl From<PraborrowStatus> for c_int {
+    fn from(status: PraborrowStatus) -> Self {
+        status.code()
+    }
+}
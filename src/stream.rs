@@ -0,0 +1,78 @@
+//! Async access to a diplomatic context (requires the `async` feature).
+
+use crate::envelope::Routing;
+use crate::safe::DiplomacyError;
+use crate::{Envelope, GlobalRegistry, Priority, logging};
+use futures_core::Stream;
+use std::future::poll_fn;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+/// Stream of envoys sent by the foreign jurisdiction.
+///
+/// Each item is woken by C-side `send_envoy`. The stream ends once relations
+/// are severed. Several streams over the same context compete for envoys;
+/// each envoy is yielded by exactly one of them.
+pub struct Incoming {
+    registry: Arc<GlobalRegistry>,
+}
+
+impl Incoming {
+    pub(crate) fn new(registry: Arc<GlobalRegistry>) -> Self {
+        Self { registry }
+    }
+}
+
+impl Stream for Incoming {
+    type Item = Envelope;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Envelope>> {
+        let registry = &self.registry;
+        registry
+            .incoming
+            .poll_pop(cx)
+            .map(|envelope| envelope.inspect(|envelope| registry.log_taken(envelope)))
+    }
+}
+
+impl GlobalRegistry {
+    /// Queues a message for the foreign jurisdiction, waiting for outbox
+    /// capacity instead of failing with [`DiplomacyError::QueueFull`], whatever
+    /// the outbox overflow policy.
+    pub(crate) async fn dispatch_async(
+        &self,
+        id: u32,
+        payload: &[u8],
+    ) -> Result<(), DiplomacyError> {
+        if self.is_closed() {
+            return Err(DiplomacyError::Closed);
+        }
+
+        tracing::debug!(
+            event = "envoy_dispatched",
+            envoy_id = id,
+            len = payload.len(),
+            payload = logging::payload(self.payload_log, payload),
+            "Envoy dispatched to foreign jurisdiction"
+        );
+
+        // Push-style delivery needs no capacity.
+        let envelope = self.seal(id, Routing::default(), payload.to_vec());
+        let Some(envelope) = self.envoy_callback.deliver(envelope) else {
+            self.outbox.count_delivered();
+            return Ok(());
+        };
+
+        // An envoy larger than the whole byte budget would wait forever.
+        let len = envelope.payload.len();
+        if !self.outbox.fits(len) {
+            return Err(DiplomacyError::QueueFull);
+        }
+        if !poll_fn(|cx| self.outbox.poll_reserve(cx, len, Priority::Normal)).await {
+            return Err(DiplomacyError::Closed);
+        }
+        self.outbox.push(envelope);
+        Ok(())
+    }
+}